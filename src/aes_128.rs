@@ -10,7 +10,7 @@ pub fn pad_pkcs7(data: &[u8]) -> Vec<u8> {
     let pad_len = 16 - (data.len() % 16);
     data.iter()
         .cloned()
-        .chain(std::iter::repeat(pad_len as u8).take(pad_len))
+        .chain(std::iter::repeat_n(pad_len as u8, pad_len))
         .collect()
 }
 
@@ -28,7 +28,7 @@ pub fn unpad_pkcs7(mut data: Vec<u8>) -> Vec<u8> {
 pub fn verify_pkcs7(data: &[u8]) -> bool {
     if let Some(&pad) = data.last() {
         let pad_usize = pad as usize;
-        if pad_usize > 0 && pad_usize <= data.len() && data.len() % 16 == 0 {
+        if pad_usize > 0 && pad_usize <= data.len() && data.len().is_multiple_of(16) {
             return data.iter().rev().take(pad_usize).all(|&byte| byte == pad);
         }
     }
@@ -54,7 +54,12 @@ pub fn encrypt_aes128_cbc(data: &[u8], key: &[u8], iv: &mut [u8]) -> Result<Vec<
 
 /// Decrypt with AES-128 in CBC mode
 pub fn decrypt_aes128_cbc(data: &[u8], key: &[u8], iv: &mut [u8]) -> Result<Vec<u8>> {
-    if data.len() % 16 != 0 {
+    decrypt_aes128_cbc_raw(data, key, iv).map(unpad_pkcs7)
+}
+
+/// Decrypt with AES-128 in CBC mode, leaving the PKCS#7 padding in place
+pub fn decrypt_aes128_cbc_raw(data: &[u8], key: &[u8], iv: &mut [u8]) -> Result<Vec<u8>> {
+    if !data.len().is_multiple_of(16) {
         return Err(anyhow!("ciphertext length must be a multiple of 16"));
     }
 
@@ -69,7 +74,7 @@ pub fn decrypt_aes128_cbc(data: &[u8], key: &[u8], iv: &mut [u8]) -> Result<Vec<
         result.extend(plain);
     });
 
-    Ok(unpad_pkcs7(result))
+    Ok(result)
 }
 
 /// Encrypt with AES-128 in ECB mode
@@ -89,7 +94,7 @@ pub fn encrypt_aes128_ecb(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
 
 /// Decrypt with AES-128 in ECB mode
 pub fn decrypt_aes128_ecb(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
-    if data.len() % 16 != 0 {
+    if !data.len().is_multiple_of(16) {
         return Err(anyhow!("ciphertext length must be a multiple of 16"));
     }
 
@@ -105,6 +110,177 @@ pub fn decrypt_aes128_ecb(data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     Ok(unpad_pkcs7(result))
 }
 
+/// How the nonce and block counter are laid out in a CTR counter block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrLayout {
+    /// 64-bit nonce followed by a 64-bit little-endian block counter, as in Cryptopals 18
+    Nonce64CounterLe64,
+    /// 96-bit nonce followed by a 32-bit big-endian block counter, as used by GCM
+    Nonce96CounterBe32,
+}
+
+impl CtrLayout {
+    /// Number of nonce bytes the layout expects
+    pub fn nonce_len(&self) -> usize {
+        match self {
+            CtrLayout::Nonce64CounterLe64 => 8,
+            CtrLayout::Nonce96CounterBe32 => 12,
+        }
+    }
+
+    fn counter_block(&self, nonce: &[u8], counter: u64) -> [u8; 16] {
+        let mut block = [0u8; 16];
+        block[..self.nonce_len()].copy_from_slice(nonce);
+        match self {
+            CtrLayout::Nonce64CounterLe64 => block[8..].copy_from_slice(&counter.to_le_bytes()),
+            CtrLayout::Nonce96CounterBe32 => {
+                block[12..].copy_from_slice(&(counter as u32).to_be_bytes())
+            }
+        }
+        block
+    }
+}
+
+/// AES-128 CTR keystream that can be read byte by byte or seeked to any offset
+#[derive(Clone)]
+pub struct CtrKeystream {
+    cipher: Aes128,
+    layout: CtrLayout,
+    nonce: Vec<u8>,
+    initial_counter: u64,
+    position: u64,
+    block: Option<(u64, [u8; 16])>,
+}
+
+impl CtrKeystream {
+    pub fn new(key: &[u8], nonce: &[u8], layout: CtrLayout) -> Result<Self> {
+        if nonce.len() != layout.nonce_len() {
+            return Err(anyhow!(
+                "nonce must be {} bytes for {:?}",
+                layout.nonce_len(),
+                layout
+            ));
+        }
+        Ok(CtrKeystream {
+            cipher: Aes128::new(GenericArray::from_slice(key)),
+            layout,
+            nonce: nonce.to_vec(),
+            initial_counter: 0,
+            position: 0,
+            block: None,
+        })
+    }
+
+    /// Start counting blocks at `counter` instead of 0 (GCM starts its payload at 2)
+    pub fn with_initial_counter(mut self, counter: u64) -> Self {
+        self.initial_counter = counter;
+        self.block = None;
+        self
+    }
+
+    /// Move to byte `offset` of the keystream
+    pub fn seek(&mut self, offset: u64) {
+        self.position = offset;
+    }
+
+    /// Current byte offset into the keystream
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Xor the keystream into `data`, advancing the position by its length
+    pub fn apply(&mut self, data: &mut [u8]) {
+        data.iter_mut()
+            .zip(self.by_ref())
+            .for_each(|(byte, key)| *byte ^= key);
+    }
+
+    fn keystream_block(&mut self, index: u64) -> [u8; 16] {
+        match self.block {
+            Some((cached, block)) if cached == index => block,
+            _ => {
+                let counter = self.initial_counter.wrapping_add(index);
+                let mut buf = GenericArray::from(self.layout.counter_block(&self.nonce, counter));
+                self.cipher.encrypt_block(&mut buf);
+                let block = buf.into();
+                self.block = Some((index, block));
+                block
+            }
+        }
+    }
+}
+
+impl Iterator for CtrKeystream {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let block = self.keystream_block(self.position / 16);
+        let byte = block[(self.position % 16) as usize];
+        self.position += 1;
+        Some(byte)
+    }
+}
+
+/// Encrypt with AES-128 in CTR mode
+pub fn encrypt_aes128_ctr(
+    data: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+) -> Result<Vec<u8>> {
+    apply_aes128_ctr_at(data, key, nonce, layout, 0)
+}
+
+/// Decrypt with AES-128 in CTR mode
+pub fn decrypt_aes128_ctr(
+    data: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+) -> Result<Vec<u8>> {
+    apply_aes128_ctr_at(data, key, nonce, layout, 0)
+}
+
+/// Encrypt or decrypt data that sits `offset` bytes into an AES-128 CTR stream
+pub fn apply_aes128_ctr_at(
+    data: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+    offset: u64,
+) -> Result<Vec<u8>> {
+    let mut keystream = CtrKeystream::new(key, nonce, layout)?;
+    keystream.seek(offset);
+    let mut result = data.to_vec();
+    keystream.apply(&mut result);
+    Ok(result)
+}
+
+/// Replace the plaintext at `offset` of an AES-128 CTR ciphertext with `newtext`, re-encrypting
+/// only the bytes that change. Writing past the end extends the ciphertext.
+pub fn edit_aes128_ctr(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+    offset: usize,
+    newtext: &[u8],
+) -> Result<Vec<u8>> {
+    if offset > ciphertext.len() {
+        return Err(anyhow!(
+            "offset {} is past the end of the ciphertext ({} bytes)",
+            offset,
+            ciphertext.len()
+        ));
+    }
+    let patch = apply_aes128_ctr_at(newtext, key, nonce, layout, offset as u64)?;
+    let mut result = ciphertext.to_vec();
+    result.truncate(offset);
+    result.extend(patch);
+    result.extend(ciphertext.iter().skip(offset + newtext.len()));
+    Ok(result)
+}
+
 fn test_aes128_cbc_mode() -> Result<(), anyhow::Error> {
     const LOREM: &str = include_str!("../data/lorem_ipsum.txt");
     const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
@@ -138,7 +314,7 @@ fn test_pkcs7_verification() -> Result<()> {
     let invalid_padding_bytes: Vec<u8> = UNPADDED
         .iter()
         .copied()
-        .chain(std::iter::repeat(5_u8).take(4))
+        .chain(std::iter::repeat_n(5_u8, 4))
         .collect();
     assert!(
         !verify_pkcs7(&invalid_padding_bytes),
@@ -149,7 +325,7 @@ fn test_pkcs7_verification() -> Result<()> {
     let invalid_padding_length: Vec<u8> = UNPADDED
         .iter()
         .copied()
-        .chain(std::iter::repeat(2_u8).take(2))
+        .chain(std::iter::repeat_n(2_u8, 2))
         .collect();
     assert!(
         !verify_pkcs7(&invalid_padding_length),
@@ -158,3 +334,84 @@ fn test_pkcs7_verification() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn challenge_18_test() -> Result<()> {
+    use crate::traits::BytesBase64Ext;
+    const DATA: &str = "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==";
+    const EXPECTED: &[u8] = b"Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ";
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+
+    let data = <Vec<u8>>::try_from_base64(DATA)?;
+    let nonce = 0u64.to_le_bytes();
+    let result = decrypt_aes128_ctr(&data, KEY, &nonce, CtrLayout::Nonce64CounterLe64)?;
+    utils::require_eq(result.as_slice(), EXPECTED)?;
+
+    let encrypted = encrypt_aes128_ctr(EXPECTED, KEY, &nonce, CtrLayout::Nonce64CounterLe64)?;
+    utils::require_eq(encrypted, data)
+}
+
+#[test]
+fn test_aes128_ctr_gcm_layout() -> Result<()> {
+    use openssl::symm::{Cipher, encrypt};
+    const LOREM: &str = include_str!("../data/lorem_ipsum.txt");
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+    const NONCE: &[u8; 12] = b"twelve bytes";
+
+    // OpenSSL's CTR mode increments the whole 16 byte block big-endian, which matches a 96/32
+    // split as long as the 32-bit counter doesn't wrap.
+    let mut iv = NONCE.to_vec();
+    iv.extend(7u32.to_be_bytes());
+    let expected = encrypt(Cipher::aes_128_ctr(), KEY, Some(&iv), LOREM.as_bytes())?;
+
+    let mut keystream =
+        CtrKeystream::new(KEY, NONCE, CtrLayout::Nonce96CounterBe32)?.with_initial_counter(7);
+    let mut result = LOREM.as_bytes().to_vec();
+    keystream.apply(&mut result);
+    utils::require_eq(result, expected)
+}
+
+#[test]
+fn test_aes128_ctr_seek() -> Result<()> {
+    const LOREM: &str = include_str!("../data/lorem_ipsum.txt");
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+    let nonce = 42u64.to_le_bytes();
+    let layout = CtrLayout::Nonce64CounterLe64;
+
+    let encrypted = encrypt_aes128_ctr(LOREM.as_bytes(), KEY, &nonce, layout)?;
+    // Any slice of the ciphertext decrypts on its own once we seek to where it starts.
+    for (start, end) in [(0, 1), (5, 37), (16, 32), (100, LOREM.len())] {
+        let part = apply_aes128_ctr_at(&encrypted[start..end], KEY, &nonce, layout, start as u64)?;
+        utils::require_eq(part.as_slice(), &LOREM.as_bytes()[start..end])?;
+    }
+
+    // Keystream bytes taken one at a time line up with the ones used for encryption.
+    let keystream = CtrKeystream::new(KEY, &nonce, layout)?;
+    let xored = utils::bytes_xor(
+        LOREM.as_bytes(),
+        &keystream.take(LOREM.len()).collect::<Vec<u8>>(),
+    );
+    utils::require_eq(xored, encrypted)?;
+
+    assert!(CtrKeystream::new(KEY, &[0; 12], layout).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_aes128_ctr_edit() -> Result<()> {
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+    let nonce = 0u64.to_le_bytes();
+    let layout = CtrLayout::Nonce64CounterLe64;
+    let encrypted = encrypt_aes128_ctr(b"Hello, World!", KEY, &nonce, layout)?;
+
+    let edited = edit_aes128_ctr(&encrypted, KEY, &nonce, layout, 7, b"Rust")?;
+    let decrypted = decrypt_aes128_ctr(&edited, KEY, &nonce, layout)?;
+    utils::require_eq(decrypted.as_slice(), b"Hello, Rustd!")?;
+
+    let extended = edit_aes128_ctr(&encrypted, KEY, &nonce, layout, 12, b"?!")?;
+    let decrypted = decrypt_aes128_ctr(&extended, KEY, &nonce, layout)?;
+    utils::require_eq(decrypted.as_slice(), b"Hello, World?!")?;
+
+    assert!(edit_aes128_ctr(&encrypted, KEY, &nonce, layout, 14, b"!").is_err());
+    Ok(())
+}
@@ -0,0 +1,227 @@
+// Big integer helpers for the public-key challenges, on top of num-bigint.
+
+use crate::{traits::BytesHexExt, types::MyBytes};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+use rand::RngCore;
+
+/// Hex and byte conversions that line up with `BytesHexExt` and `MyBytes`: big-endian bytes,
+/// hex-encoded.
+pub trait BigUintExt: Sized {
+    fn to_hex(&self) -> String;
+    /// Whitespace is ignored, so constants can be pasted straight out of an RFC.
+    fn try_from_hex(hex_string: &str) -> Result<Self, hex::FromHexError>;
+    fn to_my_bytes(&self) -> MyBytes;
+    fn from_my_bytes(bytes: &MyBytes) -> Self;
+}
+
+impl BigUintExt for BigUint {
+    fn to_hex(&self) -> String {
+        self.to_bytes_be().to_hex()
+    }
+
+    fn try_from_hex(hex_string: &str) -> Result<Self, hex::FromHexError> {
+        let mut digits: String = hex_string.split_whitespace().collect();
+        if digits.len() % 2 == 1 {
+            digits.insert(0, '0');
+        }
+        <Vec<u8>>::try_from_hex(&digits).map(|bytes| BigUint::from_bytes_be(&bytes))
+    }
+
+    fn to_my_bytes(&self) -> MyBytes {
+        MyBytes::from(self.to_bytes_be())
+    }
+
+    fn from_my_bytes(bytes: &MyBytes) -> Self {
+        BigUint::from_bytes_be(bytes)
+    }
+}
+
+/// base^exponent mod modulus. Panics if the modulus is zero, like `BigUint::modpow`.
+pub fn modpow(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+    base.modpow(exponent, modulus)
+}
+
+/// Extended Euclid: returns (g, x, y) with a*x + b*y = g = gcd(a, b)
+pub fn egcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_s, mut s) = (BigInt::one(), BigInt::zero());
+    let (mut old_t, mut t) = (BigInt::zero(), BigInt::one());
+    while !r.is_zero() {
+        let quotient = &old_r / &r;
+        (old_r, r) = (r.clone(), old_r - &quotient * &r);
+        (old_s, s) = (s.clone(), old_s - &quotient * &s);
+        (old_t, t) = (t.clone(), old_t - &quotient * &t);
+    }
+    if old_r.is_negative() {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+pub fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    a.gcd(b)
+}
+
+/// The x with a*x = 1 mod modulus, if a and modulus are coprime
+pub fn invmod(a: &BigUint, modulus: &BigUint) -> Option<BigUint> {
+    if modulus.is_zero() {
+        return None;
+    }
+    let modulus = BigInt::from(modulus.clone());
+    let (g, x, _) = egcd(&BigInt::from(a.clone()), &modulus);
+    if !g.is_one() {
+        return None;
+    }
+    x.mod_floor(&modulus).to_biguint()
+}
+
+/// Floor of the nth root of `value`
+pub fn nth_root(value: &BigUint, n: u32) -> BigUint {
+    value.nth_root(n)
+}
+
+/// The nth root of `value`, but only if it comes out exact
+pub fn exact_nth_root(value: &BigUint, n: u32) -> Option<BigUint> {
+    let root = nth_root(value, n);
+    (root.pow(n) == *value).then_some(root)
+}
+
+/// Chinese remainder theorem: the smallest x with x = residue (mod modulus) for every pair, or
+/// None if the moduli aren't pairwise coprime or one of them is zero.
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> Option<BigUint> {
+    if congruences.iter().any(|(_, modulus)| modulus.is_zero()) {
+        return None;
+    }
+    let product: BigUint = congruences.iter().map(|(_, modulus)| modulus).product();
+    let mut result = BigUint::zero();
+    for (residue, modulus) in congruences {
+        let rest = &product / modulus;
+        result += residue * &rest * invmod(&rest, modulus)?;
+    }
+    Some(result % product)
+}
+
+/// Interpret a signed value as its non-negative representative mod `modulus`
+pub fn to_residue(value: &BigInt, modulus: &BigUint) -> BigUint {
+    let modulus = BigInt::from_biguint(Sign::Plus, modulus.clone());
+    value
+        .mod_floor(&modulus)
+        .to_biguint()
+        .expect("mod_floor by a positive modulus is non-negative")
+}
+
+/// Uniform value in [0, bound), drawn from a generator of our choosing so it can be seeded
+pub fn random_below<R: RngCore + ?Sized>(rng: &mut R, bound: &BigUint) -> BigUint {
+    assert!(!bound.is_zero(), "bound must be positive");
+    let bits = bound.bits();
+    let mut bytes = vec![0u8; bits.div_ceil(8) as usize];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        // Mask off the excess high bits so rejection succeeds at least half the time.
+        if !bits.is_multiple_of(8) {
+            bytes[0] &= (1u8 << (bits % 8)) - 1;
+        }
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if candidate < *bound {
+            return candidate;
+        }
+    }
+}
+
+#[test]
+fn invmod_test() {
+    let inverse = invmod(&BigUint::from(17u32), &BigUint::from(3120u32));
+    assert_eq!(inverse, Some(BigUint::from(2753u32)));
+    assert_eq!(invmod(&BigUint::from(4u32), &BigUint::from(8u32)), None);
+    assert_eq!(invmod(&BigUint::from(4u32), &BigUint::zero()), None);
+
+    let (g, x, y) = egcd(&BigInt::from(240), &BigInt::from(46));
+    assert_eq!(g, BigInt::from(2));
+    assert_eq!(BigInt::from(240) * x + BigInt::from(46) * y, g);
+    assert_eq!(
+        gcd(&BigUint::from(240u32), &BigUint::from(46u32)),
+        BigUint::from(2u32)
+    );
+}
+
+#[test]
+fn modpow_test() {
+    let result = modpow(
+        &BigUint::from(4u32),
+        &BigUint::from(13u32),
+        &BigUint::from(497u32),
+    );
+    assert_eq!(result, BigUint::from(445u32));
+    assert_eq!(
+        to_residue(&BigInt::from(-3), &BigUint::from(7u32)),
+        BigUint::from(4u32)
+    );
+}
+
+#[test]
+fn nth_root_test() {
+    let base = BigUint::try_from_hex("deadbeefcafebabe0123456789abcdef").unwrap();
+    let cube = base.pow(3);
+    assert_eq!(nth_root(&cube, 3), base);
+    assert_eq!(exact_nth_root(&cube, 3), Some(base.clone()));
+    assert_eq!(nth_root(&(&cube + 1u32), 3), base);
+    assert_eq!(exact_nth_root(&(&cube + 1u32), 3), None);
+}
+
+#[test]
+fn crt_test() {
+    let congruences = [
+        (BigUint::from(2u32), BigUint::from(3u32)),
+        (BigUint::from(3u32), BigUint::from(5u32)),
+        (BigUint::from(2u32), BigUint::from(7u32)),
+    ];
+    assert_eq!(crt(&congruences), Some(BigUint::from(23u32)));
+    let not_coprime = [
+        (BigUint::from(1u32), BigUint::from(4u32)),
+        (BigUint::from(3u32), BigUint::from(6u32)),
+    ];
+    assert_eq!(crt(&not_coprime), None);
+    let zero_modulus = [
+        (BigUint::from(2u32), BigUint::from(3u32)),
+        (BigUint::from(1u32), BigUint::zero()),
+    ];
+    assert_eq!(crt(&zero_modulus), None);
+}
+
+#[test]
+fn bignum_conversion_test() {
+    let value = BigUint::try_from_hex("FFFFFFFF FFFFFFFF\n C90FDAA2 2168C234").unwrap();
+    assert_eq!(value.to_hex(), "ffffffffffffffffc90fdaa22168c234");
+    assert_eq!(
+        BigUint::try_from_hex("abc").unwrap(),
+        BigUint::from(0xabcu32)
+    );
+    assert!(BigUint::try_from_hex("xyz").is_err());
+
+    let bytes = value.to_my_bytes();
+    assert_eq!(bytes.to_hex(), value.to_hex());
+    assert_eq!(BigUint::from_my_bytes(&bytes), value);
+    assert_eq!(
+        BigUint::from_my_bytes(&MyBytes::from("YELLOW SUBMARINE"))
+            .to_my_bytes()
+            .to_string(),
+        "YELLOW SUBMARINE"
+    );
+}
+
+#[test]
+fn random_below_test() {
+    use crate::mt19937::Mt19937;
+    let bound = BigUint::from(1000u32);
+    let mut rng = Mt19937::new(5489);
+    let values: Vec<_> = (0..200).map(|_| random_below(&mut rng, &bound)).collect();
+    assert!(values.iter().all(|value| *value < bound));
+    assert!(values.iter().any(|value| *value >= BigUint::from(500u32)));
+
+    // Same seed, same values.
+    let mut again = Mt19937::new(5489);
+    assert_eq!(random_below(&mut again, &bound), values[0]);
+}
@@ -0,0 +1,121 @@
+use crate::utils;
+use anyhow::{Result, anyhow};
+
+const BLOCK_SIZE: usize = 16;
+
+/// How a ciphertext byte maps onto the plaintext byte we want to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitflipMode {
+    /// Flipping a bit in one block flips the same bit in the next block's plaintext (and
+    /// scrambles the block we flipped).
+    Cbc,
+    /// Flipping a bit flips the same bit of the plaintext at the same position.
+    Ctr,
+}
+
+/// Patch `ciphertext` so that the plaintext `known`, which starts at byte `offset` of the
+/// plaintext, decrypts to `desired` instead. For CBC every changed byte is patched in the block in
+/// front of it, which scrambles that block when it decrypts. So no change can sit in the first
+/// block (its block in front is the IV), and the block in front of each changed block must not be
+/// part of the target.
+pub fn bitflip(
+    ciphertext: &[u8],
+    offset: usize,
+    known: &[u8],
+    desired: &[u8],
+    mode: BitflipMode,
+) -> Result<Vec<u8>> {
+    if known.len() != desired.len() {
+        return Err(anyhow!(
+            "known ({} bytes) and desired ({} bytes) plaintext must be the same length",
+            known.len(),
+            desired.len()
+        ));
+    }
+    let target = offset..offset + known.len();
+    if target.end > ciphertext.len() {
+        return Err(anyhow!("target runs past the end of the ciphertext"));
+    }
+
+    let mut patched = ciphertext.to_vec();
+    let changes = target
+        .clone()
+        .zip(utils::bytes_xor(known, desired))
+        .filter(|&(_, flip)| flip != 0);
+    for (position, flip) in changes {
+        let patch_position = match mode {
+            BitflipMode::Ctr => position,
+            BitflipMode::Cbc => {
+                if position < BLOCK_SIZE {
+                    return Err(anyhow!("CBC can't patch the first block without the IV"));
+                }
+                let scrambled = position / BLOCK_SIZE - 1;
+                let scrambled_range = scrambled * BLOCK_SIZE..(scrambled + 1) * BLOCK_SIZE;
+                if scrambled_range.start < target.end && target.start < scrambled_range.end {
+                    return Err(anyhow!(
+                        "CBC patch would scramble block {}, which is part of the target",
+                        scrambled
+                    ));
+                }
+                position - BLOCK_SIZE
+            }
+        };
+        patched[patch_position] ^= flip;
+    }
+    Ok(patched)
+}
+
+/// Where to put a CBC target behind a prefix of `prefix_len` bytes that we don't control.
+/// Returns how many filler bytes to send before the target, so that it starts on a block
+/// boundary with one whole block of our filler in front of it to sacrifice, and the plaintext
+/// offset the target ends up at.
+pub fn cbc_target_layout(prefix_len: usize) -> (usize, usize) {
+    let filler = (BLOCK_SIZE - prefix_len % BLOCK_SIZE) % BLOCK_SIZE + BLOCK_SIZE;
+    (filler, prefix_len + filler)
+}
+
+#[test]
+fn bitflip_rejects_bad_targets() {
+    let ciphertext = [0u8; 48];
+    assert!(bitflip(&ciphertext, 20, b"AAAA", b"AAA", BitflipMode::Ctr).is_err());
+    assert!(bitflip(&ciphertext, 46, b"AAAA", b"BBBB", BitflipMode::Ctr).is_err());
+    assert!(bitflip(&ciphertext, 4, b"AAAA", b"BBBB", BitflipMode::Cbc).is_err());
+    // The change in block 2 would have to be patched from block 1, which holds the rest.
+    assert!(bitflip(&ciphertext, 30, b"AAAA", b"BBBB", BitflipMode::Cbc).is_err());
+    assert!(bitflip(&ciphertext, 4, b"AAAA", b"BBBB", BitflipMode::Ctr).is_ok());
+    assert!(bitflip(&ciphertext, 28, b"AAAA", b"BBBB", BitflipMode::Cbc).is_ok());
+}
+
+#[test]
+fn bitflip_cbc_across_blocks() -> Result<()> {
+    use crate::aes_128;
+    let key = utils::generate_16_byte_key();
+    let plaintext = [b'A'; 64];
+    let ciphertext = aes_128::encrypt_aes128_cbc(&plaintext, &key, &mut [0u8; 16])?;
+
+    // The target crosses into block 2, but only block 1 changes, so only block 0 is sacrificed.
+    let patched = bitflip(&ciphertext, 28, &[b'A'; 8], b"BBBBAAAA", BitflipMode::Cbc)?;
+    let decrypted = aes_128::decrypt_aes128_cbc_raw(&patched, &key, &mut [0u8; 16])?;
+    assert_eq!(
+        &decrypted[16..64],
+        b"AAAAAAAAAAAABBBBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+    );
+
+    // Changes in two adjacent blocks can't both be patched, since the second patch lands in the
+    // first changed block. Blocks further apart each get patched from the block in front.
+    let patched = bitflip(&ciphertext, 32, &[b'A'; 32], &[b'C'; 32], BitflipMode::Cbc);
+    assert!(patched.is_err());
+    let patched = bitflip(&ciphertext, 48, &[b'A'; 16], &[b'C'; 16], BitflipMode::Cbc)?;
+    let patched = bitflip(&patched, 16, &[b'A'; 4], b"DDDD", BitflipMode::Cbc)?;
+    let decrypted = aes_128::decrypt_aes128_cbc_raw(&patched, &key, &mut [0u8; 16])?;
+    assert_eq!(&decrypted[16..20], b"DDDD");
+    assert_eq!(&decrypted[48..64], &[b'C'; 16]);
+    Ok(())
+}
+
+#[test]
+fn cbc_target_layout_test() {
+    assert_eq!(cbc_target_layout(32), (16, 48));
+    assert_eq!(cbc_target_layout(5), (27, 32));
+    assert_eq!(cbc_target_layout(0), (16, 16));
+}
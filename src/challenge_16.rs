@@ -1,17 +1,30 @@
 use crate::{
     aes_128::{self, pad_pkcs7},
+    bitflip::{self, BitflipMode},
     utils,
 };
 use anyhow::anyhow;
 
+pub const PREPEND: &str = "comment1=cooking%20MCs;userdata=";
+pub const APPEND: &str = ";comment2=%20like%20a%20pound%20of%20bacon";
+
+// Quote out ";" and "=" from data and wrap it following challenge instructions.
+pub fn format_userdata(data: &str) -> String {
+    let clean_data = data.replace("=", "\"=\"").replace(";", "\";\"");
+    format!("{}{}{}", PREPEND, clean_data, APPEND)
+}
+
+// Look for characters ";admin=true;"
+pub fn is_admin(plaintext: &[u8]) -> bool {
+    String::from_utf8_lossy(plaintext)
+        .split(";")
+        .any(|string| string.contains("admin=true"))
+}
+
 // Formats the data following challenge instructions, then returns the encrypted data.
 fn challenge_16_oracle(data: &str, key: &[u8], iv: Vec<u8>) -> Result<Vec<u8>, anyhow::Error> {
-    const PREPEND: &str = "comment1=cooking%20MCs;userdata=";
-    const APPEND: &str = ";comment2=%20like%20a%20pound%20of%20bacon";
-    // Quote out ";" and "=" from data.
-    let clean_data = data.replace("=", "\"=\"").replace(";", "\";\"");
     // format the data and convert to bytes.
-    let full_data = format!("{}{}{}", PREPEND, clean_data, APPEND);
+    let full_data = format_userdata(data);
     let padded_data = aes_128::pad_pkcs7(full_data.as_bytes());
     // Pad out the input to the 16-byte AES block length and encrypt it under the random AES key.
     aes_128::encrypt_aes128_cbc(&padded_data, key, &mut iv.clone())
@@ -20,36 +33,30 @@ fn challenge_16_oracle(data: &str, key: &[u8], iv: Vec<u8>) -> Result<Vec<u8>, a
 fn challenge_16_decryptor(data: &[u8], key: &[u8], iv: Vec<u8>) -> Result<bool, anyhow::Error> {
     // Decrypt string
     let decrypted_data = aes_128::decrypt_aes128_cbc(data, key, &mut iv.clone())?;
-    let decrypted_data_string = String::from_utf8_lossy(&decrypted_data);
-    println!("{}", decrypted_data_string);
-    // Look for characters ";admin=true;"
-    for string in decrypted_data_string.split(";") {
-        // If characters exist, return true.
-        if string.contains("admin=true") {
-            return Ok(true);
-        } else {
-            continue;
-        }
-    }
-    Ok(false)
+    Ok(is_admin(&decrypted_data))
 }
 
 // For this challenge we alter the encrypted data to make ourselves admin.
 fn challenge_16() -> Result<(), anyhow::Error> {
-    // Input string, we will change the second block to our target.
-    const INPUT: &str = "AAAAAAAAAAAAAAAAAadminAtrueAAAAA";
+    const KNOWN: &str = "AAAAAAAAAAAA";
+    const TARGET: &[u8] = b";admin=true;";
     // generate a random AES key
     let key = utils::generate_16_byte_key();
     // iv shouldn't really matter, so we generate a random one using the random key generator.
     let iv = utils::generate_16_byte_key();
+    // Pad the prefix out to a block boundary and add a block to sacrifice, then the target.
+    let (filler, offset) = bitflip::cbc_target_layout(PREPEND.len());
+    let input = "A".repeat(filler) + KNOWN;
     // Let oracle encrypt the data after formatting it according to challenge instructions.
-    let mut encrypted = challenge_16_oracle(INPUT, &key, iv.clone().to_vec())?;
-    // First we xor the encrypted bytes with 'A' to get the corresponding byte used to xor our
-    // target bytes. then we xor these with the characters we need before replacing them in our
-    // cipher. We know the prepend is 2 blocks long, so we adjust our index accordingly.
-    for (i, c) in [(32, b';'), (38, b'='), (43, b'=')].iter() {
-        encrypted[*i] ^= b'A' ^ c;
-    }
+    let encrypted = challenge_16_oracle(&input, &key, iv.clone().to_vec())?;
+    // Flipping bits in our sacrificial block flips the same bits in the block after it.
+    let encrypted = bitflip::bitflip(
+        &encrypted,
+        offset,
+        KNOWN.as_bytes(),
+        TARGET,
+        BitflipMode::Cbc,
+    )?;
     // We decrypt our cipher to check if we're now admin.
     if challenge_16_decryptor(&encrypted, &key, iv.clone().to_vec())? {
         Ok(())
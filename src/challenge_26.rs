@@ -0,0 +1,51 @@
+use crate::{
+    aes_128::{self, CtrLayout},
+    bitflip::{self, BitflipMode},
+    challenge_16::{PREPEND, format_userdata, is_admin},
+    utils,
+};
+use anyhow::anyhow;
+
+// Same formatting as challenge 16, but encrypted under CTR.
+fn challenge_26_oracle(data: &str, key: &[u8], nonce: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let full_data = format_userdata(data);
+    aes_128::encrypt_aes128_ctr(
+        full_data.as_bytes(),
+        key,
+        nonce,
+        CtrLayout::Nonce64CounterLe64,
+    )
+}
+
+fn challenge_26_decryptor(data: &[u8], key: &[u8], nonce: &[u8]) -> Result<bool, anyhow::Error> {
+    let decrypted_data =
+        aes_128::decrypt_aes128_ctr(data, key, nonce, CtrLayout::Nonce64CounterLe64)?;
+    Ok(is_admin(&decrypted_data))
+}
+
+// CTR flips the plaintext right where we flip the ciphertext, so no block gets sacrificed.
+fn challenge_26() -> Result<(), anyhow::Error> {
+    const INPUT: &str = "AAAAAAAAAAAA";
+    const TARGET: &[u8] = b";admin=true;";
+    let key = utils::generate_16_byte_key();
+    let nonce: [u8; 8] = rand::random();
+
+    let encrypted = challenge_26_oracle(INPUT, &key, &nonce)?;
+    let encrypted = bitflip::bitflip(
+        &encrypted,
+        PREPEND.len(),
+        INPUT.as_bytes(),
+        TARGET,
+        BitflipMode::Ctr,
+    )?;
+    if challenge_26_decryptor(&encrypted, &key, &nonce)? {
+        Ok(())
+    } else {
+        Err(anyhow!("admin=true not found"))
+    }
+}
+
+#[test]
+fn challenge_26_test() -> Result<(), anyhow::Error> {
+    challenge_26()
+}
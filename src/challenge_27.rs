@@ -0,0 +1,87 @@
+use crate::{aes_128, challenge_16::format_userdata, utils};
+use anyhow::anyhow;
+use std::fmt;
+
+// The receiver's complaint about non-ASCII plaintext. Helpfully, it includes the plaintext.
+#[derive(Debug)]
+pub struct NonAsciiError {
+    pub plaintext: Vec<u8>,
+}
+
+impl fmt::Display for NonAsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid ASCII in plaintext: {:?}", self.plaintext)
+    }
+}
+
+impl std::error::Error for NonAsciiError {}
+
+// Same as the challenge 16 oracle, except the key doubles as the IV.
+fn challenge_27_oracle(data: &str, key: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let full_data = format_userdata(data);
+    aes_128::encrypt_aes128_cbc(full_data.as_bytes(), key, &mut key.to_vec())
+}
+
+// Decrypts and rejects anything with high-ASCII bytes in it.
+fn challenge_27_receiver(data: &[u8], key: &[u8]) -> Result<(), anyhow::Error> {
+    let plaintext = aes_128::decrypt_aes128_cbc(data, key, &mut key.to_vec())?;
+    if !plaintext.is_ascii() {
+        return Err(NonAsciiError { plaintext }.into());
+    }
+    Ok(())
+}
+
+// Send C1 || 0 || C1 to the receiver. The first block decrypts to D(C1) ^ IV and the third to
+// D(C1) ^ 0, so xoring the leaked plaintext blocks gives us the IV, which is the key.
+pub fn recover_key<F>(ciphertext: &[u8], mut receiver: F) -> Result<Vec<u8>, anyhow::Error>
+where
+    F: FnMut(&[u8]) -> Result<(), anyhow::Error>,
+{
+    // The original tail has to come after the forged blocks, or the receiver strips padding off
+    // D(C1) and the third block comes back short.
+    if ciphertext.len() <= 48 {
+        return Err(anyhow!("need more than 3 blocks of ciphertext"));
+    }
+    let mut forged = ciphertext[..16].to_vec();
+    forged.extend([0; 16]);
+    forged.extend_from_slice(&ciphertext[..16]);
+    // Keep the original tail so the padding still checks out.
+    forged.extend_from_slice(&ciphertext[48..]);
+
+    let error = match receiver(&forged) {
+        Ok(()) => return Err(anyhow!("receiver accepted the forged ciphertext")),
+        Err(error) => error,
+    };
+    let leaked = error
+        .downcast_ref::<NonAsciiError>()
+        .ok_or(anyhow!("receiver didn't leak the plaintext: {}", error))?;
+    if leaked.plaintext.len() < 48 {
+        return Err(anyhow!("leaked plaintext is too short"));
+    }
+    Ok(utils::bytes_xor(
+        &leaked.plaintext[..16],
+        &leaked.plaintext[32..48],
+    ))
+}
+
+fn challenge_27() -> Result<(), anyhow::Error> {
+    let key = utils::generate_16_byte_key();
+    let ciphertext = challenge_27_oracle("AAAAAAAAAAAAAAAA", &key)?;
+    // Honest traffic goes through fine.
+    challenge_27_receiver(&ciphertext, &key)?;
+
+    let recovered = recover_key(&ciphertext, |data| challenge_27_receiver(data, &key))?;
+    utils::require_eq(recovered, key)
+}
+
+#[test]
+fn challenge_27_test() -> Result<(), anyhow::Error> {
+    challenge_27()?;
+    // Three blocks would leave the forged block last.
+    let key = utils::generate_16_byte_key();
+    let ciphertext = challenge_27_oracle("", &key)?;
+    utils::require(
+        recover_key(&ciphertext[..48], |data| challenge_27_receiver(data, &key)).is_err(),
+        "three blocks shouldn't be enough",
+    )
+}
@@ -0,0 +1,40 @@
+// Two-way in-process channels for the protocol simulations: each party owns one end and blocks
+// on the other.
+
+use anyhow::{Result, anyhow};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// One end of a two-way channel
+pub struct Endpoint<T> {
+    tx: Sender<T>,
+    rx: Receiver<T>,
+}
+
+impl<T> Endpoint<T> {
+    pub fn send(&self, message: T) -> Result<()> {
+        self.tx
+            .send(message)
+            .map_err(|_| anyhow!("the other side hung up"))
+    }
+
+    pub fn recv(&self) -> Result<T> {
+        self.rx
+            .recv()
+            .map_err(|_| anyhow!("the other side hung up"))
+    }
+}
+
+pub fn connect<T>() -> (Endpoint<T>, Endpoint<T>) {
+    let (left_tx, right_rx) = mpsc::channel();
+    let (right_tx, left_rx) = mpsc::channel();
+    (
+        Endpoint {
+            tx: left_tx,
+            rx: left_rx,
+        },
+        Endpoint {
+            tx: right_tx,
+            rx: right_rx,
+        },
+    )
+}
@@ -0,0 +1,64 @@
+use crate::{
+    aes_128::{self, CtrLayout},
+    utils,
+};
+use anyhow::Result;
+
+const LAYOUT: CtrLayout = CtrLayout::Nonce64CounterLe64;
+
+/// Seekable CTR storage from Cryptopals 25. It keeps the key to itself but lets anyone rewrite
+/// parts of a ciphertext.
+pub struct EditOracle {
+    key: Vec<u8>,
+    nonce: [u8; 8],
+}
+
+impl EditOracle {
+    pub fn new() -> Self {
+        EditOracle {
+            key: utils::generate_16_byte_key(),
+            nonce: rand::random(),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        aes_128::encrypt_aes128_ctr(plaintext, &self.key, &self.nonce, LAYOUT)
+    }
+
+    pub fn edit(&self, ciphertext: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        aes_128::edit_aes128_ctr(ciphertext, &self.key, &self.nonce, LAYOUT, offset, newtext)
+    }
+}
+
+impl Default for EditOracle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recover the plaintext behind a CTR ciphertext through an edit function. Overwriting everything
+/// with zeros makes the edit hand back the raw keystream.
+pub fn recover_plaintext<F>(ciphertext: &[u8], mut edit: F) -> Result<Vec<u8>>
+where
+    F: FnMut(&[u8], usize, &[u8]) -> Result<Vec<u8>>,
+{
+    let keystream = edit(ciphertext, 0, &vec![0; ciphertext.len()])?;
+    Ok(utils::bytes_xor(ciphertext, &keystream))
+}
+
+#[test]
+fn challenge_25_test() -> Result<()> {
+    use crate::traits::BytesBase64Ext;
+    const DATA: &str = include_str!("../data/challenge_7_data.txt");
+    const KEY: &[u8; 16] = b"YELLOW SUBMARINE";
+
+    let data = <Vec<u8>>::try_from_base64(&DATA.replace("\n", ""))?;
+    let plaintext = aes_128::decrypt_aes128_ecb(&data, KEY)?;
+
+    let oracle = EditOracle::new();
+    let ciphertext = oracle.encrypt(&plaintext)?;
+    let recovered = recover_plaintext(&ciphertext, |ciphertext, offset, newtext| {
+        oracle.edit(ciphertext, offset, newtext)
+    })?;
+    utils::require_eq(recovered, plaintext)
+}
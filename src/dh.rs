@@ -0,0 +1,168 @@
+// Diffie–Hellman over a prime field (Cryptopals 33).
+
+use crate::{
+    bignum::{self, BigUintExt},
+    hash::Hash,
+    sha1::Sha1,
+    sha256::Sha256,
+};
+use anyhow::{Result, anyhow};
+use num_bigint::BigUint;
+use rand::RngCore;
+
+/// The 1536-bit MODP prime from RFC 3526, which is the one Cryptopals calls "NIST"
+pub const MODP_1536_P: &str = "
+    ffffffff ffffffff c90fdaa2 2168c234 c4c6628b 80dc1cd1 29024e08 8a67cc74
+    020bbea6 3b139b22 514a0879 8e3404dd ef9519b3 cd3a431b 302b0a6d f25f1437
+    4fe1356d 6d51c245 e485b576 625e7ec6 f44c42e9 a637ed6b 0bff5cb6 f406b7ed
+    ee386bfb 5a899fa5 ae9f2411 7c4b1fe6 49286651 ece45b3d c2007cb8 a163bf05
+    98da4836 1c55d39a 69163fa8 fd24cf5f 83655d23 dca3ad96 1c62f356 208552bb
+    9ed52907 7096966d 670c354e 4abc9804 f1746c08 ca237327 ffffffff ffffffff";
+pub const MODP_1536_G: u32 = 2;
+
+/// How a shared secret gets turned into an AES-128 key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKdf {
+    /// First 16 bytes of SHA1(s), as in Cryptopals 34 onwards
+    Sha1,
+    /// First 16 bytes of SHA256(s)
+    Sha256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhGroup {
+    pub p: BigUint,
+    pub g: BigUint,
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyPair {
+    pub private: BigUint,
+    pub public: BigUint,
+}
+
+impl DhGroup {
+    /// A group with a sensible generator: p > 3 and g in [2, p - 2]
+    pub fn new(p: BigUint, g: BigUint) -> Result<Self> {
+        let group = Self::new_any_generator(p, g)?;
+        if group.g < BigUint::from(2u32) || group.g > &group.p - 2u32 {
+            return Err(anyhow!(
+                "generator {} is degenerate mod {}",
+                group.g,
+                group.p
+            ));
+        }
+        Ok(group)
+    }
+
+    /// Take the generator on trust, as the Cryptopals 35 Bob does; only the modulus is checked,
+    /// so key generation can't fail.
+    pub fn new_any_generator(p: BigUint, g: BigUint) -> Result<Self> {
+        if p <= BigUint::from(3u32) {
+            return Err(anyhow!("modulus {} is too small", p));
+        }
+        Ok(DhGroup { p, g })
+    }
+
+    pub fn modp_1536() -> Self {
+        DhGroup {
+            p: BigUint::try_from_hex(MODP_1536_P).expect("MODP prime is valid hex"),
+            g: BigUint::from(MODP_1536_G),
+        }
+    }
+
+    /// Pick a private exponent in [1, p - 1) and compute g^a mod p
+    pub fn generate_keypair<R: RngCore + ?Sized>(&self, rng: &mut R) -> KeyPair {
+        let private = bignum::random_below(rng, &(&self.p - 2u32)) + 1u32;
+        self.keypair_from_private(private)
+    }
+
+    pub fn keypair_from_private(&self, private: BigUint) -> KeyPair {
+        let public = bignum::modpow(&self.g, &private, &self.p);
+        KeyPair { private, public }
+    }
+
+    /// s = B^a mod p
+    pub fn shared_secret(&self, private: &BigUint, their_public: &BigUint) -> BigUint {
+        bignum::modpow(their_public, private, &self.p)
+    }
+}
+
+/// Derive a 16-byte key from the big-endian bytes of a shared secret, ready for
+/// `aes_128::encrypt_aes128_cbc`.
+pub fn session_key(secret: &BigUint, kdf: SessionKdf) -> Vec<u8> {
+    let bytes = secret.to_bytes_be();
+    let mut digest = match kdf {
+        SessionKdf::Sha1 => Sha1::digest(&bytes),
+        SessionKdf::Sha256 => Sha256::digest(&bytes),
+    };
+    digest.truncate(16);
+    digest
+}
+
+#[test]
+fn challenge_33_small_test() -> Result<()> {
+    use crate::mt19937::Mt19937;
+    use num_traits::One;
+    let group = DhGroup::new(BigUint::from(37u32), BigUint::from(5u32))?;
+    let mut rng = Mt19937::new(33);
+    for _ in 0..20 {
+        let alice = group.generate_keypair(&mut rng);
+        let bob = group.generate_keypair(&mut rng);
+        assert!(alice.private >= BigUint::one() && alice.private < BigUint::from(36u32));
+        assert_eq!(
+            group.shared_secret(&alice.private, &bob.public),
+            group.shared_secret(&bob.private, &alice.public)
+        );
+    }
+    let keypair = group.keypair_from_private(BigUint::from(6u32));
+    assert_eq!(keypair.public, BigUint::from(11u32));
+    Ok(())
+}
+
+#[test]
+fn dh_group_validation_test() {
+    let group = |p: u32, g: u32| DhGroup::new(BigUint::from(p), BigUint::from(g));
+    for p in [0, 1, 2, 3] {
+        assert!(group(p, 2).is_err());
+    }
+    for g in [0, 1, 36, 37, 40] {
+        assert!(group(37, g).is_err());
+    }
+    assert!(group(37, 2).is_ok() && group(37, 35).is_ok());
+
+    // The trusting constructor lets a bad generator through, but not a bad modulus.
+    assert!(DhGroup::new_any_generator(BigUint::from(37u32), BigUint::from(36u32)).is_ok());
+    assert!(DhGroup::new_any_generator(BigUint::from(2u32), BigUint::from(1u32)).is_err());
+}
+
+#[test]
+fn challenge_33_test() -> Result<()> {
+    use crate::{aes_128, mt19937::Mt19937, utils};
+    let group = DhGroup::modp_1536();
+    let openssl_p = openssl::bn::BigNum::get_rfc3526_prime_1536()?;
+    utils::require_eq(group.p.to_bytes_be(), openssl_p.to_vec())?;
+
+    let mut rng = Mt19937::new(1_700_000_000);
+    let alice = group.generate_keypair(&mut rng);
+    let bob = group.generate_keypair(&mut rng);
+    let secret = group.shared_secret(&alice.private, &bob.public);
+    utils::require_eq(&secret, &group.shared_secret(&bob.private, &alice.public))?;
+
+    // Seeding the generator the same way gives the same keys.
+    let mut replay = Mt19937::new(1_700_000_000);
+    utils::require_eq(&group.generate_keypair(&mut replay).private, &alice.private)?;
+
+    for kdf in [SessionKdf::Sha1, SessionKdf::Sha256] {
+        let key = session_key(&secret, kdf);
+        utils::require_eq(key.len(), 16)?;
+        let iv = utils::generate_16_byte_key();
+        let ciphertext = aes_128::encrypt_aes128_cbc(b"hello bob", &key, &mut iv.clone())?;
+        let plaintext = aes_128::decrypt_aes128_cbc(&ciphertext, &key, &mut iv.clone())?;
+        utils::require_eq(plaintext.as_slice(), b"hello bob".as_slice())?;
+    }
+    utils::require(
+        session_key(&secret, SessionKdf::Sha1) != session_key(&secret, SessionKdf::Sha256),
+        "KDFs should disagree",
+    )
+}
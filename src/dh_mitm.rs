@@ -0,0 +1,564 @@
+// The Cryptopals 34/35 echo protocol, played out between Alice and Bob threads talking over
+// channels, with a pluggable Mallory sitting on the wire between them.
+
+use crate::{
+    aes_128,
+    channel::{self, Endpoint},
+    dh::{self, DhGroup, SessionKdf},
+    mt19937::Mt19937,
+};
+use anyhow::{Result, anyhow};
+use num_bigint::BigUint;
+use rand::RngCore;
+use std::thread;
+
+const KDF: SessionKdf = SessionKdf::Sha1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// A->B: the group and Alice's public key
+    Key {
+        p: BigUint,
+        g: BigUint,
+        public: BigUint,
+    },
+    /// A->B: the group Alice wants to use, before any keys (Cryptopals 35)
+    Group { p: BigUint, g: BigUint },
+    /// B->A: Bob agrees to the group
+    Ack,
+    /// Either public key, once the group is settled; in the Cryptopals 34 handshake only Bob's
+    Public(BigUint),
+    /// AES-CBC(SHA1(s)[0:16], iv, message) || iv, either way
+    Data(Vec<u8>),
+}
+
+/// How Alice opens the conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handshake {
+    /// Group and key in one go, as in Cryptopals 34
+    KeyExchange,
+    /// Agree on the group first, then swap keys, as in Cryptopals 35
+    Negotiated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    AliceToBob,
+    BobToAlice,
+}
+
+/// Whoever sits between Alice and Bob. Gets every message on the wire and decides what to pass
+/// on in its place.
+pub trait Mallory {
+    fn intercept(&mut self, direction: Direction, message: Message) -> Result<Message>;
+}
+
+/// Encrypt under a session key with a fresh IV, sent along after the ciphertext
+pub fn encrypt_message<R: RngCore + ?Sized>(
+    key: &[u8],
+    plaintext: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>> {
+    let mut iv = [0u8; 16];
+    rng.fill_bytes(&mut iv);
+    let mut data = aes_128::encrypt_aes128_cbc(plaintext, key, &mut iv.clone())?;
+    data.extend_from_slice(&iv);
+    Ok(data)
+}
+
+/// Decrypt a ciphertext || iv message. Bad padding is an error, which is how Mallory tells a
+/// wrong guess at the key from a right one.
+pub fn decrypt_message(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 32 || !data.len().is_multiple_of(16) {
+        return Err(anyhow!("message is {} bytes", data.len()));
+    }
+    let (ciphertext, iv) = data.split_at(data.len() - 16);
+    let plaintext = aes_128::decrypt_aes128_cbc_raw(ciphertext, key, &mut iv.to_vec())?;
+    if !aes_128::verify_pkcs7(&plaintext) {
+        return Err(anyhow!("bad padding"));
+    }
+    Ok(aes_128::unpad_pkcs7(plaintext))
+}
+
+/// Alice agrees on a key with Bob, then sends each message and checks it comes back. Returns
+/// what Bob echoed.
+pub fn alice<R: RngCore + ?Sized>(
+    link: &Endpoint<Message>,
+    group: &DhGroup,
+    handshake: Handshake,
+    messages: &[&[u8]],
+    rng: &mut R,
+) -> Result<Vec<Vec<u8>>> {
+    let keypair = group.generate_keypair(rng);
+    match handshake {
+        Handshake::KeyExchange => link.send(Message::Key {
+            p: group.p.clone(),
+            g: group.g.clone(),
+            public: keypair.public.clone(),
+        })?,
+        Handshake::Negotiated => {
+            link.send(Message::Group {
+                p: group.p.clone(),
+                g: group.g.clone(),
+            })?;
+            match link.recv()? {
+                Message::Ack => {}
+                other => return Err(anyhow!("alice expected an ack, got {:?}", other)),
+            }
+            link.send(Message::Public(keypair.public.clone()))?;
+        }
+    }
+    let their_public = match link.recv()? {
+        Message::Public(public) => public,
+        other => return Err(anyhow!("alice expected a public key, got {:?}", other)),
+    };
+    let key = dh::session_key(&group.shared_secret(&keypair.private, &their_public), KDF);
+
+    let mut echoed = Vec::with_capacity(messages.len());
+    for message in messages {
+        link.send(Message::Data(encrypt_message(&key, message, rng)?))?;
+        match link.recv()? {
+            Message::Data(data) => echoed.push(decrypt_message(&key, &data)?),
+            other => return Err(anyhow!("alice expected data, got {:?}", other)),
+        }
+    }
+    Ok(echoed)
+}
+
+/// Bob follows whichever handshake Alice starts, answering her key with his own, then echoes every
+/// message back under a fresh IV until Alice hangs up.
+pub fn bob<R: RngCore + ?Sized>(link: &Endpoint<Message>, rng: &mut R) -> Result<()> {
+    let mut group = None;
+    let mut key = None;
+    while let Ok(message) = link.recv() {
+        match message {
+            Message::Group { p, g } => {
+                // Whatever generator shows up is taken at its word, which is the hole
+                // Cryptopals 35 pokes at.
+                group = Some(DhGroup::new_any_generator(p, g)?);
+                link.send(Message::Ack)?;
+            }
+            Message::Public(public) => {
+                let group = group
+                    .as_ref()
+                    .ok_or(anyhow!("bob got a key before a group"))?;
+                let keypair = group.generate_keypair(rng);
+                link.send(Message::Public(keypair.public.clone()))?;
+                key = Some(dh::session_key(
+                    &group.shared_secret(&keypair.private, &public),
+                    KDF,
+                ));
+            }
+            Message::Key { p, g, public } => {
+                let group = DhGroup::new(p, g)?;
+                let keypair = group.generate_keypair(rng);
+                link.send(Message::Public(keypair.public.clone()))?;
+                key = Some(dh::session_key(
+                    &group.shared_secret(&keypair.private, &public),
+                    KDF,
+                ));
+            }
+            Message::Data(data) => {
+                let key = key.as_ref().ok_or(anyhow!("bob got data before a key"))?;
+                let plaintext = decrypt_message(key, &data)?;
+                link.send(Message::Data(encrypt_message(key, &plaintext, rng)?))?;
+            }
+            other => return Err(anyhow!("bob didn't expect {:?}", other)),
+        }
+    }
+    Ok(())
+}
+
+/// Pass messages between the two sides through `mallory`. The protocol is strictly
+/// request/response, so this alternates until Alice hangs up.
+pub fn relay<M: Mallory + ?Sized>(
+    alice_side: &Endpoint<Message>,
+    bob_side: &Endpoint<Message>,
+    mallory: &mut M,
+) -> Result<()> {
+    while let Ok(message) = alice_side.recv() {
+        bob_side.send(mallory.intercept(Direction::AliceToBob, message)?)?;
+        let reply = bob_side.recv()?;
+        alice_side.send(mallory.intercept(Direction::BobToAlice, reply)?)?;
+    }
+    Ok(())
+}
+
+/// Run the whole echo protocol, with `mallory` on the wire if there is one. Alice and Bob draw
+/// their keys and IVs from MT19937s seeded with `seed` and `seed + 1`.
+pub fn run_echo(
+    group: &DhGroup,
+    handshake: Handshake,
+    messages: &[&[u8]],
+    mallory: Option<&mut (dyn Mallory + Send)>,
+    seed: u32,
+) -> Result<Vec<Vec<u8>>> {
+    let (alice_link, alice_wire) = channel::connect();
+    thread::scope(|scope| {
+        // With a Mallory, Bob gets a wire of his own and the relay joins the two.
+        let (bob_link, relay_ends) = match mallory {
+            Some(mallory) => {
+                let (bob_wire, bob_link) = channel::connect();
+                (bob_link, Some((alice_wire, bob_wire, mallory)))
+            }
+            None => (alice_wire, None),
+        };
+        let bob_thread =
+            scope.spawn(move || bob(&bob_link, &mut Mt19937::new(seed.wrapping_add(1))));
+        let relay_thread = relay_ends.map(|(alice_wire, bob_wire, mallory)| {
+            scope.spawn(move || relay(&alice_wire, &bob_wire, mallory))
+        });
+
+        let echoed = alice(
+            &alice_link,
+            group,
+            handshake,
+            messages,
+            &mut Mt19937::new(seed),
+        );
+        // Hanging up lets the relay and Bob wind down.
+        drop(alice_link);
+        if let Some(relay_thread) = relay_thread {
+            relay_thread
+                .join()
+                .map_err(|_| anyhow!("relay panicked"))??;
+        }
+        bob_thread.join().map_err(|_| anyhow!("bob panicked"))??;
+        echoed
+    })
+}
+
+/// Passes everything through untouched
+pub struct Eavesdropper {
+    pub seen: Vec<Message>,
+}
+
+impl Mallory for Eavesdropper {
+    fn intercept(&mut self, _direction: Direction, message: Message) -> Result<Message> {
+        self.seen.push(message.clone());
+        Ok(message)
+    }
+}
+
+/// Cryptopals 34: swap both public keys for p, so both sides compute s = p^x mod p = 0, then read
+/// every message going by.
+#[derive(Default)]
+pub struct KeyFixing {
+    p: Option<BigUint>,
+    pub decrypted: Vec<Vec<u8>>,
+}
+
+impl Mallory for KeyFixing {
+    fn intercept(&mut self, _direction: Direction, message: Message) -> Result<Message> {
+        match message {
+            Message::Key { p, g, .. } => {
+                self.p = Some(p.clone());
+                Ok(Message::Key {
+                    public: p.clone(),
+                    p,
+                    g,
+                })
+            }
+            Message::Public(_) => {
+                let p = self
+                    .p
+                    .clone()
+                    .ok_or(anyhow!("bob replied before alice spoke"))?;
+                Ok(Message::Public(p))
+            }
+            Message::Data(data) => {
+                let key = dh::session_key(&BigUint::ZERO, KDF);
+                self.decrypted.push(decrypt_message(&key, &data)?);
+                Ok(Message::Data(data))
+            }
+            other => Ok(other),
+        }
+    }
+}
+
+/// The generator Mallory slips Bob during negotiation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadGenerator {
+    /// g = 1: every public key is 1
+    One,
+    /// g = p: every public key is 0
+    P,
+    /// g = p - 1: public keys are 1 or p - 1, depending on the parity of the exponent
+    PMinusOne,
+}
+
+/// A message Mallory read, and the secret that opened it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered {
+    pub direction: Direction,
+    pub secret: BigUint,
+    pub plaintext: Vec<u8>,
+}
+
+/// Cryptopals 35: negotiate a degenerate generator with Bob and hand him g in place of Alice's
+/// key. Bob's public key B = g^b then doubles as his shared secret (g^b again), and Alice's secret
+/// B^a can only be one of a couple of values. Mallory reads both sides and re-encrypts in between,
+/// since the two secrets needn't match.
+pub struct MaliciousGenerator {
+    pub generator: BadGenerator,
+    pub recovered: Vec<Recovered>,
+    p: Option<BigUint>,
+    bob_secret: Option<BigUint>,
+    alice_candidates: Vec<BigUint>,
+}
+
+impl MaliciousGenerator {
+    pub fn new(generator: BadGenerator) -> Self {
+        MaliciousGenerator {
+            generator,
+            recovered: Vec::new(),
+            p: None,
+            bob_secret: None,
+            alice_candidates: Vec::new(),
+        }
+    }
+
+    fn bad_g(&self, p: &BigUint) -> BigUint {
+        match self.generator {
+            BadGenerator::One => BigUint::from(1u32),
+            BadGenerator::P => p.clone(),
+            BadGenerator::PMinusOne => p - 1u32,
+        }
+    }
+
+    // Whatever Alice's exponent, B^a can only land on these.
+    fn predict_alice_secrets(p: &BigUint, bob_public: &BigUint) -> Vec<BigUint> {
+        if *bob_public == p - 1u32 {
+            vec![BigUint::from(1u32), p - 1u32]
+        } else {
+            vec![bob_public.clone()]
+        }
+    }
+
+    // Try every candidate secret and stick with the one that decrypts cleanly. A wrong key still
+    // turns up valid padding now and then, so two that both work leave the question open.
+    fn decrypt_from_alice(&mut self, data: &[u8]) -> Result<(BigUint, Vec<u8>)> {
+        let mut opened: Vec<_> = self
+            .alice_candidates
+            .iter()
+            .filter_map(|secret| {
+                decrypt_message(&dh::session_key(secret, KDF), data)
+                    .ok()
+                    .map(|plaintext| (secret.clone(), plaintext))
+            })
+            .collect();
+        match opened.len() {
+            0 => Err(anyhow!(
+                "none of the predicted secrets decrypt alice's message"
+            )),
+            1 => {
+                let (secret, plaintext) = opened.remove(0);
+                self.alice_candidates = vec![secret.clone()];
+                Ok((secret, plaintext))
+            }
+            _ => {
+                // Only the survivors stay in the running for the next message.
+                self.alice_candidates = opened.into_iter().map(|(secret, _)| secret).collect();
+                Err(anyhow!(
+                    "{} predicted secrets decrypt alice's message",
+                    self.alice_candidates.len()
+                ))
+            }
+        }
+    }
+}
+
+impl Mallory for MaliciousGenerator {
+    fn intercept(&mut self, direction: Direction, message: Message) -> Result<Message> {
+        match (direction, message) {
+            (Direction::AliceToBob, Message::Group { p, .. }) => {
+                let g = self.bad_g(&p);
+                self.p = Some(p.clone());
+                Ok(Message::Group { p, g })
+            }
+            (Direction::AliceToBob, Message::Public(_)) => {
+                let p = self.p.as_ref().ok_or(anyhow!("no group negotiated"))?;
+                Ok(Message::Public(self.bad_g(p)))
+            }
+            (Direction::BobToAlice, Message::Public(bob_public)) => {
+                let p = self.p.as_ref().ok_or(anyhow!("no group negotiated"))?;
+                self.alice_candidates = Self::predict_alice_secrets(p, &bob_public);
+                self.bob_secret = Some(bob_public.clone());
+                Ok(Message::Public(bob_public))
+            }
+            (direction, Message::Data(data)) => {
+                let bob_secret = self
+                    .bob_secret
+                    .clone()
+                    .ok_or(anyhow!("no keys exchanged"))?;
+                let (secret, plaintext, forward_secret) = match direction {
+                    Direction::AliceToBob => {
+                        let (secret, plaintext) = self.decrypt_from_alice(&data)?;
+                        (secret, plaintext, bob_secret)
+                    }
+                    Direction::BobToAlice => {
+                        let plaintext = decrypt_message(&dh::session_key(&bob_secret, KDF), &data)?;
+                        let alice_secret = self.alice_candidates[0].clone();
+                        (bob_secret, plaintext, alice_secret)
+                    }
+                };
+                let forwarded = encrypt_message(
+                    &dh::session_key(&forward_secret, KDF),
+                    &plaintext,
+                    &mut rand::rng(),
+                )?;
+                self.recovered.push(Recovered {
+                    direction,
+                    secret,
+                    plaintext,
+                });
+                Ok(Message::Data(forwarded))
+            }
+            (_, other) => Ok(other),
+        }
+    }
+}
+
+#[cfg(test)]
+const MESSAGES: [&[u8]; 3] = [
+    b"hello bob",
+    b"YELLOW SUBMARINE",
+    b"the quick brown fox jumps over the lazy dog",
+];
+
+#[test]
+fn echo_protocol_test() -> Result<()> {
+    let group = DhGroup::modp_1536();
+    assert_eq!(
+        run_echo(&group, Handshake::KeyExchange, &MESSAGES, None, 34)?,
+        MESSAGES
+    );
+
+    // A relay that doesn't touch anything is invisible, and sees nothing but ciphertext.
+    let mut eavesdropper = Eavesdropper { seen: Vec::new() };
+    assert_eq!(
+        run_echo(
+            &group,
+            Handshake::KeyExchange,
+            &MESSAGES,
+            Some(&mut eavesdropper),
+            34
+        )?,
+        MESSAGES
+    );
+    assert_eq!(eavesdropper.seen.len(), 2 + 2 * MESSAGES.len());
+
+    assert_eq!(
+        run_echo(&group, Handshake::Negotiated, &MESSAGES, None, 35)?,
+        MESSAGES
+    );
+    Ok(())
+}
+
+#[test]
+fn challenge_34_test() -> Result<()> {
+    let group = DhGroup::modp_1536();
+    let mut mallory = KeyFixing::default();
+    let echoed = run_echo(
+        &group,
+        Handshake::KeyExchange,
+        &MESSAGES,
+        Some(&mut mallory),
+        34,
+    )?;
+    assert_eq!(echoed, MESSAGES);
+    // Alice's message and Bob's echo of it, every time.
+    let expected: Vec<&[u8]> = MESSAGES.iter().flat_map(|message| [*message; 2]).collect();
+    assert_eq!(mallory.decrypted, expected);
+    Ok(())
+}
+
+#[test]
+fn challenge_35_test() -> Result<()> {
+    use std::collections::HashSet;
+    let group = DhGroup::modp_1536();
+    let p_minus_one = &group.p - 1u32;
+    let strategies = [
+        (BadGenerator::One, vec![BigUint::from(1u32)]),
+        (BadGenerator::P, vec![BigUint::ZERO]),
+        (
+            BadGenerator::PMinusOne,
+            vec![BigUint::from(1u32), p_minus_one.clone()],
+        ),
+    ];
+
+    for (generator, candidates) in strategies {
+        let mut winners = HashSet::new();
+        // Enough seeds to see both parities of Alice's and Bob's exponents.
+        for seed in 0..8 {
+            let mut mallory = MaliciousGenerator::new(generator);
+            let echoed = run_echo(
+                &group,
+                Handshake::Negotiated,
+                &MESSAGES,
+                Some(&mut mallory),
+                seed,
+            )?;
+            assert_eq!(echoed, MESSAGES);
+
+            let plaintexts: Vec<&[u8]> = mallory
+                .recovered
+                .iter()
+                .map(|recovered| recovered.plaintext.as_slice())
+                .collect();
+            let expected: Vec<&[u8]> = MESSAGES.iter().flat_map(|message| [*message; 2]).collect();
+            assert_eq!(plaintexts, expected);
+            for recovered in mallory.recovered {
+                assert!(candidates.contains(&recovered.secret));
+                if recovered.direction == Direction::AliceToBob {
+                    winners.insert(recovered.secret);
+                }
+            }
+        }
+        assert_eq!(winners.len(), candidates.len());
+    }
+    Ok(())
+}
+
+#[test]
+fn ambiguous_alice_secret_test() -> Result<()> {
+    let p = DhGroup::modp_1536().p;
+    let candidates = vec![BigUint::from(1u32), &p - 1u32];
+    let keys: Vec<_> = candidates
+        .iter()
+        .map(|secret| dh::session_key(secret, KDF))
+        .collect();
+
+    // Find a block both keys decrypt to the same last byte, then pick the IV that turns that byte
+    // into 0x01: valid padding either way.
+    let mut rng = Mt19937::new(35);
+    let (block, iv) = loop {
+        let mut block = [0u8; 16];
+        rng.fill_bytes(&mut block);
+        let [right, wrong] = [&keys[0], &keys[1]]
+            .map(|key| aes_128::decrypt_aes128_cbc_raw(&block, key, &mut [0; 16]));
+        let (right, wrong) = (right?, wrong?);
+        if right[15] == wrong[15] {
+            let mut iv = [0u8; 16];
+            iv[15] = right[15] ^ 1;
+            break (block, iv);
+        }
+    };
+    let mut data = block.to_vec();
+    data.extend_from_slice(&iv);
+
+    let mut mallory = MaliciousGenerator::new(BadGenerator::PMinusOne);
+    mallory.alice_candidates = candidates.clone();
+    assert!(mallory.decrypt_from_alice(&data).is_err());
+    assert_eq!(mallory.alice_candidates, candidates);
+
+    // A proper message under one key settles it.
+    let message = encrypt_message(&keys[1], b"settled", &mut rng)?;
+    let (secret, plaintext) = mallory.decrypt_from_alice(&message)?;
+    assert_eq!(
+        (secret, plaintext),
+        (candidates[1].clone(), b"settled".to_vec())
+    );
+    assert_eq!(mallory.alice_candidates, [candidates[1].clone()]);
+    Ok(())
+}
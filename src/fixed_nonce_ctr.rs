@@ -0,0 +1,156 @@
+use crate::{single_byte_xor, utils};
+
+/// How ciphertexts of different lengths get lined up before breaking the shared keystream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Cut every ciphertext down to the shortest one, turning the problem into repeating-key xor.
+    Truncate,
+    /// Keep going past the shortest ciphertext for as long as at least `min_samples` ciphertexts
+    /// reach a column. Later columns are scored with fewer samples.
+    Ragged { min_samples: usize },
+}
+
+/// Transpose the ciphertexts into columns of bytes that were xored with the same keystream byte.
+pub fn columns(ciphertexts: &[Vec<u8>], alignment: Alignment) -> Vec<Vec<u8>> {
+    let length = match alignment {
+        Alignment::Truncate => ciphertexts.iter().map(Vec::len).min().unwrap_or(0),
+        Alignment::Ragged { min_samples } => (0..)
+            .take_while(|&i| {
+                ciphertexts.iter().filter(|c| c.len() > i).count() >= min_samples.max(1)
+            })
+            .count(),
+    };
+    (0..length)
+        .map(|i| {
+            ciphertexts
+                .iter()
+                .filter_map(|c| c.get(i).copied())
+                .collect()
+        })
+        .collect()
+}
+
+/// Recover the keystream shared by ciphertexts encrypted under a reused CTR nonce. Every column
+/// is a single-byte xor, so we break them one at a time.
+pub fn recover_keystream(ciphertexts: &[Vec<u8>], alignment: Alignment) -> Vec<u8> {
+    columns(ciphertexts, alignment)
+        .iter()
+        .filter_map(|column| single_byte_xor::try_break(column))
+        .map(|result| result.byte)
+        .collect()
+}
+
+/// Decrypt as much of every ciphertext as the keystream covers.
+pub fn decrypt_all(ciphertexts: &[Vec<u8>], keystream: &[u8]) -> Vec<Vec<u8>> {
+    ciphertexts
+        .iter()
+        .map(|c| {
+            let length = c.len().min(keystream.len());
+            utils::bytes_xor(&c[..length], &keystream[..length])
+        })
+        .collect()
+}
+
+/// Average english score of the decrypted ciphertexts, lower is better.
+pub fn score_keystream(ciphertexts: &[Vec<u8>], keystream: &[u8]) -> f32 {
+    let plaintexts = decrypt_all(ciphertexts, keystream);
+    plaintexts
+        .iter()
+        .map(|p| utils::english_score_bytes(p))
+        .sum::<f32>()
+        / plaintexts.len().max(1) as f32
+}
+
+#[cfg(test)]
+fn encrypt_lines(key: &[u8]) -> Result<(Vec<Vec<u8>>, Vec<u8>), anyhow::Error> {
+    use crate::aes_128::{self, CtrKeystream, CtrLayout};
+    const FILE: &str = include_str!("../data/challenge_7_expected.txt");
+    let nonce = 0u64.to_le_bytes();
+
+    let ciphertexts = FILE
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            aes_128::encrypt_aes128_ctr(line.as_bytes(), key, &nonce, CtrLayout::Nonce64CounterLe64)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let longest = ciphertexts.iter().map(Vec::len).max().unwrap_or(0);
+    let keystream = CtrKeystream::new(key, &nonce, CtrLayout::Nonce64CounterLe64)?
+        .take(longest)
+        .collect();
+    Ok((ciphertexts, keystream))
+}
+
+#[cfg(test)]
+fn matching_bytes(left: &[u8], right: &[u8]) -> usize {
+    left.iter().zip(right).filter(|(l, r)| l == r).count()
+}
+
+#[test]
+fn challenge_20_test() -> Result<(), anyhow::Error> {
+    let key = utils::generate_16_byte_key();
+    let (ciphertexts, keystream) = encrypt_lines(&key)?;
+
+    let recovered = recover_keystream(&ciphertexts, Alignment::Truncate);
+    let shortest = ciphertexts.iter().map(Vec::len).min().unwrap_or(0);
+    utils::require_eq(recovered.len(), shortest)?;
+    let hits = matching_bytes(&recovered, &keystream);
+    utils::require(
+        hits * 10 >= recovered.len() * 9,
+        &format!(
+            "only {} of {} keystream bytes recovered",
+            hits,
+            recovered.len()
+        ),
+    )?;
+    utils::require(
+        score_keystream(&ciphertexts, &recovered) < score_keystream(&ciphertexts, &[0; 1]),
+        "recovered keystream should decrypt to english",
+    )
+}
+
+#[test]
+fn challenge_19_ragged_test() -> Result<(), anyhow::Error> {
+    const MIN_SAMPLES: usize = 8;
+    let key = utils::generate_16_byte_key();
+    let (ciphertexts, keystream) = encrypt_lines(&key)?;
+
+    let truncated = recover_keystream(&ciphertexts, Alignment::Truncate);
+    let ragged = recover_keystream(
+        &ciphertexts,
+        Alignment::Ragged {
+            min_samples: MIN_SAMPLES,
+        },
+    );
+    // Ragged alignment reaches well past the shortest ciphertext.
+    utils::require(
+        ragged.len() > truncated.len() * 2,
+        &format!("ragged keystream too short: {}", ragged.len()),
+    )?;
+    let hits = matching_bytes(&ragged, &keystream);
+    utils::require(
+        hits * 4 >= ragged.len() * 3,
+        &format!(
+            "only {} of {} keystream bytes recovered",
+            hits,
+            ragged.len()
+        ),
+    )
+}
+
+#[test]
+fn columns_test() {
+    let ciphertexts = vec![vec![1, 2, 3], vec![4, 5], vec![6, 7, 8, 9]];
+    assert_eq!(
+        columns(&ciphertexts, Alignment::Truncate),
+        vec![vec![1, 4, 6], vec![2, 5, 7]]
+    );
+    assert_eq!(
+        columns(&ciphertexts, Alignment::Ragged { min_samples: 2 }),
+        vec![vec![1, 4, 6], vec![2, 5, 7], vec![3, 8]]
+    );
+    assert_eq!(
+        columns(&ciphertexts, Alignment::Ragged { min_samples: 1 }).len(),
+        4
+    );
+}
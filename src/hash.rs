@@ -0,0 +1,216 @@
+use std::fmt::Debug;
+
+/// An incremental hash function: enough to build an HMAC on top of.
+pub trait Hash: Clone {
+    const BLOCK_SIZE: usize;
+    const OUTPUT_SIZE: usize;
+
+    fn new() -> Self;
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self) -> Vec<u8>;
+
+    fn digest(data: &[u8]) -> Vec<u8> {
+        let mut hash = Self::new();
+        hash.update(data);
+        hash.finalize()
+    }
+}
+
+/// A Merkle–Damgård hash whose chaining state can be picked back up from a published digest,
+/// which is all a length-extension attack needs.
+pub trait MerkleDamgard: Hash {
+    /// The padding appended to a message of `message_len` bytes
+    fn padding(message_len: u64) -> Vec<u8>;
+
+    /// Resume hashing from `digest`, as if `processed_len` bytes (padding included) had already
+    /// been hashed to produce it.
+    fn from_digest(digest: &[u8], processed_len: u64) -> Option<Self>;
+}
+
+/// Block size shared by MD4, MD5, SHA-1 and SHA-256
+pub const BLOCK_SIZE: usize = 64;
+
+/// The byte order a hash reads its message words in, writes its length field in and spells its
+/// registers out in for the digest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+impl Endian {
+    pub fn read_word(self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self {
+            Endian::Big => u32::from_be_bytes(bytes),
+            Endian::Little => u32::from_le_bytes(bytes),
+        }
+    }
+
+    pub fn write_word(self, word: u32) -> [u8; 4] {
+        match self {
+            Endian::Big => word.to_be_bytes(),
+            Endian::Little => word.to_le_bytes(),
+        }
+    }
+}
+
+/// What sets one of the 32-bit Merkle–Damgård hashes apart from the others. `MdHash` supplies
+/// the buffering, padding and digest encoding around it.
+pub trait Compression: Clone + Debug {
+    /// The chaining registers
+    type State: AsRef<[u32]> + AsMut<[u32]> + Copy + Default + Debug + PartialEq;
+    const INITIAL_STATE: Self::State;
+    const ENDIAN: Endian;
+
+    /// Run the compression function over a single 64-byte block
+    fn compress(state: &mut Self::State, block: &[u8]);
+}
+
+/// The padding appended to a message of `message_len` bytes: 0x80, zeros, then the length in
+/// bits as a u64 in the hash's byte order.
+pub fn padding(message_len: u64, endian: Endian) -> Vec<u8> {
+    let zeros = (BLOCK_SIZE * 2 - 9 - (message_len as usize % BLOCK_SIZE)) % BLOCK_SIZE;
+    let bit_len = message_len.wrapping_mul(8);
+    let mut padding = vec![0x80];
+    padding.extend(std::iter::repeat_n(0, zeros));
+    padding.extend(match endian {
+        Endian::Big => bit_len.to_be_bytes(),
+        Endian::Little => bit_len.to_le_bytes(),
+    });
+    padding
+}
+
+/// A Merkle–Damgård hash with its chaining state out in the open, so it can be resumed from a
+/// digest or driven block by block.
+#[derive(Clone, Debug)]
+pub struct MdHash<C: Compression> {
+    pub state: C::State,
+    /// Number of message bytes hashed so far, including whatever sits in the buffer
+    pub length: u64,
+    buffer: Vec<u8>,
+}
+
+impl<C: Compression> MdHash<C> {
+    /// Resume hashing from arbitrary registers, as if `length` bytes (a whole number of blocks,
+    /// padding included) had already been processed.
+    pub fn from_state(state: C::State, length: u64) -> Self {
+        MdHash {
+            state,
+            length,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+        }
+    }
+
+    /// Read the registers back out of a digest
+    pub fn state_from_digest(digest: &[u8]) -> Option<C::State> {
+        if digest.len() != Self::OUTPUT_SIZE {
+            return None;
+        }
+        let mut state = C::State::default();
+        for (register, word) in state.as_mut().iter_mut().zip(digest.chunks(4)) {
+            *register = C::ENDIAN.read_word(word);
+        }
+        Some(state)
+    }
+}
+
+impl<C: Compression> Default for MdHash<C> {
+    fn default() -> Self {
+        Self::from_state(C::INITIAL_STATE, 0)
+    }
+}
+
+impl<C: Compression> Hash for MdHash<C> {
+    const BLOCK_SIZE: usize = BLOCK_SIZE;
+    const OUTPUT_SIZE: usize = std::mem::size_of::<C::State>();
+
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.buffer.extend_from_slice(data);
+        let full = self.buffer.len() - self.buffer.len() % BLOCK_SIZE;
+        for block in self.buffer[..full].chunks(BLOCK_SIZE) {
+            C::compress(&mut self.state, block);
+        }
+        self.buffer.drain(..full);
+    }
+
+    fn finalize(mut self) -> Vec<u8> {
+        let padding = Self::padding(self.length);
+        self.update(&padding);
+        self.state
+            .as_ref()
+            .iter()
+            .flat_map(|&word| C::ENDIAN.write_word(word))
+            .collect()
+    }
+}
+
+impl<C: Compression> MerkleDamgard for MdHash<C> {
+    fn padding(message_len: u64) -> Vec<u8> {
+        padding(message_len, C::ENDIAN)
+    }
+
+    fn from_digest(digest: &[u8], processed_len: u64) -> Option<Self> {
+        Self::state_from_digest(digest).map(|state| Self::from_state(state, processed_len))
+    }
+}
+
+/// Compare `H` with a reference implementation on random messages around the padding edge cases
+#[cfg(test)]
+pub fn check_against_reference<H: Hash>(reference: impl Fn(&[u8]) -> Vec<u8>) {
+    for length in [0, 1, 55, 56, 63, 64, 65, 119, 120, 1000] {
+        let data: Vec<u8> = (0..length).map(|_| rand::random()).collect();
+        assert_eq!(H::digest(&data), reference(&data), "length {}", length);
+    }
+}
+
+/// Hashing the padded message block by hand gives the registers the digest shows, and picking up
+/// from those registers continues the same hash.
+#[cfg(test)]
+fn check_state_roundtrip<C: Compression>() {
+    const MESSAGE: &[u8] = b"YELLOW SUBMARINE";
+    let mut padded = MESSAGE.to_vec();
+    padded.extend(MdHash::<C>::padding(MESSAGE.len() as u64));
+    assert!(padded.len().is_multiple_of(BLOCK_SIZE));
+    let mut state = C::INITIAL_STATE;
+    padded
+        .chunks(BLOCK_SIZE)
+        .for_each(|block| C::compress(&mut state, block));
+    assert_eq!(
+        Some(state),
+        MdHash::<C>::state_from_digest(&MdHash::<C>::digest(MESSAGE))
+    );
+    assert_eq!(MdHash::<C>::state_from_digest(&[0; 15]), None);
+
+    let mut resumed = MdHash::<C>::from_state(state, padded.len() as u64);
+    resumed.update(b";admin=true");
+    padded.extend_from_slice(b";admin=true");
+    assert_eq!(resumed.finalize(), MdHash::<C>::digest(&padded));
+}
+
+#[test]
+fn md_state_roundtrip_test() {
+    use crate::{
+        md4::Md4Compression, md5::Md5Compression, sha1::Sha1Compression, sha256::Sha256Compression,
+    };
+    check_state_roundtrip::<Md4Compression>();
+    check_state_roundtrip::<Md5Compression>();
+    check_state_roundtrip::<Sha1Compression>();
+    check_state_roundtrip::<Sha256Compression>();
+}
+
+#[test]
+fn padding_test() {
+    // 0x80, then zeros up to 56 mod 64, then the bit length.
+    let padding_55 = padding(55, Endian::Big);
+    assert_eq!(padding_55, [0x80, 0, 0, 0, 0, 0, 0, 0x01, 0xb8]);
+    let padding_56 = padding(56, Endian::Little);
+    assert_eq!(padding_56.len(), 72);
+    assert_eq!(padding_56[0], 0x80);
+    assert_eq!(padding_56[64..], [0xc0, 0x01, 0, 0, 0, 0, 0, 0]);
+}
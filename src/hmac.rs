@@ -0,0 +1,231 @@
+use crate::hash::Hash;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// HMAC (RFC 2104) over any hash: H((key ^ opad) || H((key ^ ipad) || message))
+#[derive(Clone, Debug)]
+pub struct Hmac<H> {
+    inner: H,
+    outer: H,
+}
+
+impl<H: Hash> Hmac<H> {
+    pub fn new(key: &[u8]) -> Self {
+        // Keys longer than a block get hashed down first, then everything is zero-padded.
+        let mut block_key = if key.len() > H::BLOCK_SIZE {
+            H::digest(key)
+        } else {
+            key.to_vec()
+        };
+        block_key.resize(H::BLOCK_SIZE, 0);
+
+        let mut inner = H::new();
+        inner.update(&block_key.iter().map(|byte| byte ^ IPAD).collect::<Vec<_>>());
+        let mut outer = H::new();
+        outer.update(&block_key.iter().map(|byte| byte ^ OPAD).collect::<Vec<_>>());
+        Hmac { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut outer = self.outer;
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+
+    pub fn mac(key: &[u8], message: &[u8]) -> Vec<u8> {
+        let mut hmac = Self::new(key);
+        hmac.update(message);
+        hmac.finalize()
+    }
+
+    pub fn verify(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+        constant_time_eq(&Self::mac(key, message), tag)
+    }
+}
+
+/// Compare two tags without an early exit, so the time taken doesn't say how much of a guess was
+/// right (the leak `hmac_timing` exploits). Only the lengths are allowed to show.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0, |difference, (l, r)| difference | (l ^ r))
+            == 0
+}
+
+#[cfg(test)]
+const TRUNCATION_MESSAGE: &[u8] = b"Test With Truncation";
+#[cfg(test)]
+const LARGE_KEY_MESSAGE: &[u8] = b"Test Using Larger Than Block-Size Key - Hash Key First";
+
+/// Keys 0x01, 0x02, ..., 0x19 from test case 4 of both RFCs
+#[cfg(test)]
+fn counting_key() -> Vec<u8> {
+    (1..=25).collect()
+}
+
+/// Check (key, message, expected hex) vectors. Expected values shorter than the digest are the
+/// RFCs' truncated-output cases and only cover the leading bytes.
+#[cfg(test)]
+fn check_vectors<H: Hash>(vectors: &[(Vec<u8>, &[u8], &str)]) {
+    use crate::traits::BytesHexExt;
+    for (key, message, expected) in vectors {
+        let tag = Hmac::<H>::mac(key, message).to_hex();
+        assert_eq!(tag.len(), H::OUTPUT_SIZE * 2);
+        assert_eq!(&tag[..expected.len()], *expected);
+    }
+}
+
+#[test]
+fn hmac_md5_rfc_2202_test() {
+    use crate::md5::Md5;
+    check_vectors::<Md5>(&[
+        (
+            vec![0x0b; 16],
+            b"Hi There",
+            "9294727a3638bb1c13f48ef8158bfc9d",
+        ),
+        (
+            b"Jefe".to_vec(),
+            b"what do ya want for nothing?",
+            "750c783e6ab0b503eaa86e310a5db738",
+        ),
+        (
+            vec![0xaa; 16],
+            &[0xdd; 50],
+            "56be34521d144c88dbb8c733f0e8b3f6",
+        ),
+        (
+            counting_key(),
+            &[0xcd; 50],
+            "697eaf0aca3a3aea3a75164746ffaa79",
+        ),
+        (
+            vec![0x0c; 16],
+            TRUNCATION_MESSAGE,
+            "56461ef2342edc00f9bab995",
+        ),
+        (
+            vec![0xaa; 80],
+            LARGE_KEY_MESSAGE,
+            "6b1ab7fe4bd7bf8f0b62e6ce61b9d0cd",
+        ),
+        (
+            vec![0xaa; 80],
+            b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data",
+            "6f630fad67cda0ee1fb1f562db3aa53e",
+        ),
+    ]);
+}
+
+#[test]
+fn hmac_sha1_rfc_2202_test() {
+    use crate::sha1::Sha1;
+    check_vectors::<Sha1>(&[
+        (
+            vec![0x0b; 20],
+            b"Hi There",
+            "b617318655057264e28bc0b6fb378c8ef146be00",
+        ),
+        (
+            b"Jefe".to_vec(),
+            b"what do ya want for nothing?",
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
+        ),
+        (
+            vec![0xaa; 20],
+            &[0xdd; 50],
+            "125d7342b9ac11cd91a39af48aa17b4f63f175d3",
+        ),
+        (
+            counting_key(),
+            &[0xcd; 50],
+            "4c9007f4026250c6bc8414f9bf50c86c2d7235da",
+        ),
+        (
+            vec![0x0c; 20],
+            TRUNCATION_MESSAGE,
+            "4c1a03424b55e07fe7f27be1",
+        ),
+        (
+            vec![0xaa; 80],
+            LARGE_KEY_MESSAGE,
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112",
+        ),
+        (
+            vec![0xaa; 80],
+            b"Test Using Larger Than Block-Size Key and Larger Than One Block-Size Data",
+            "e8e99d0f45237d786d6bbaa7965c7808bbff1a91",
+        ),
+    ]);
+}
+
+#[test]
+fn hmac_sha256_rfc_4231_test() {
+    use crate::sha256::Sha256;
+    check_vectors::<Sha256>(&[
+        (
+            vec![0x0b; 20],
+            b"Hi There",
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+        ),
+        (
+            b"Jefe".to_vec(),
+            b"what do ya want for nothing?",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        ),
+        (
+            vec![0xaa; 20],
+            &[0xdd; 50],
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+        ),
+        (
+            counting_key(),
+            &[0xcd; 50],
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+        ),
+        (
+            vec![0x0c; 20],
+            TRUNCATION_MESSAGE,
+            "a3b6167473100ee06e0c796c2955552b",
+        ),
+        (
+            vec![0xaa; 131],
+            LARGE_KEY_MESSAGE,
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+        ),
+        (
+            vec![0xaa; 131],
+            b"This is a test using a larger than block-size key and a larger than block-size data. \
+              The key needs to be hashed before being used by the HMAC algorithm.",
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+        ),
+    ]);
+}
+
+#[test]
+fn constant_time_eq_test() {
+    assert!(constant_time_eq(b"", b""));
+    assert!(constant_time_eq(b"YELLOW", b"YELLOW"));
+    assert!(!constant_time_eq(b"YELLOW", b"YELLOw"));
+    assert!(!constant_time_eq(b"YELLOW", b"YELLOW SUBMARINE"));
+}
+
+#[test]
+fn hmac_incremental_test() {
+    use crate::sha256::Sha256;
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+    const MESSAGE: &[u8] = b"comment1=cooking%20MCs;userdata=foo";
+    let mut hmac = Hmac::<Sha256>::new(KEY);
+    MESSAGE.chunks(7).for_each(|chunk| hmac.update(chunk));
+    let tag = hmac.finalize();
+    assert_eq!(tag, Hmac::<Sha256>::mac(KEY, MESSAGE));
+    assert!(Hmac::<Sha256>::verify(KEY, MESSAGE, &tag));
+    assert!(!Hmac::<Sha256>::verify(b"yellow submarine", MESSAGE, &tag));
+}
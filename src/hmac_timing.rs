@@ -0,0 +1,301 @@
+// The HMAC-SHA1 timing leak from Cryptopals 31/32: a tiny local web app that checks signatures
+// with an early-exit, sleep-per-byte comparison, and an attacker that reads the MAC off its
+// response times.
+
+use crate::{
+    hash::Hash,
+    sha1::{self, Sha1},
+    traits::BytesHexExt,
+};
+use anyhow::{Result, anyhow};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Byte-at-a-time comparison that bails at the first mismatch and sleeps after every byte that
+/// matches.
+pub fn insecure_compare(left: &[u8], right: &[u8], delay: Duration) -> bool {
+    for (a, b) in left.iter().zip(right) {
+        if a != b {
+            return false;
+        }
+        thread::sleep(delay);
+    }
+    left.len() == right.len()
+}
+
+/// Local stand-in for the challenge's web app. Answers `GET /test?file=...&signature=...` with 200
+/// when the signature is HMAC-SHA1(key, file) and 500 otherwise. Stops when dropped.
+pub struct TimingLeakServer {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TimingLeakServer {
+    /// Start serving on an ephemeral 127.0.0.1 port. Only the first `mac_len` bytes of the HMAC
+    /// are checked, so tests don't have to wait out all 20; anything from 1 to 20 is allowed.
+    pub fn start(key: &[u8], delay: Duration, mac_len: usize) -> Result<Self> {
+        if mac_len == 0 || mac_len > Sha1::OUTPUT_SIZE {
+            return Err(anyhow!(
+                "can check 1 to {} bytes of the HMAC, not {}",
+                Sha1::OUTPUT_SIZE,
+                mac_len
+            ));
+        }
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let key = key.to_vec();
+        let stop = shutdown.clone();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    // A client hanging up early is its own problem, not the server's.
+                    let _ = handle_request(stream, &key, delay, mac_len);
+                }
+            }
+        });
+
+        Ok(TimingLeakServer {
+            addr,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for TimingLeakServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop up so it notices.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_request(stream: TcpStream, key: &[u8], delay: Duration, mac_len: usize) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so closing the socket doesn't reset the connection under the client.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let status = match parse_query(&request_line) {
+        Some((file, signature)) => {
+            let mac = sha1::hmac_sha1(key, file.as_bytes());
+            let valid = <Vec<u8>>::try_from_hex(&signature)
+                .is_ok_and(|signature| insecure_compare(&signature, &mac[..mac_len], delay));
+            if valid {
+                "200 OK"
+            } else {
+                "500 Internal Server Error"
+            }
+        }
+        None => "404 Not Found",
+    };
+    write!(
+        reader.get_mut(),
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    )?;
+    Ok(())
+}
+
+// Pull `file` and `signature` out of "GET /test?file=...&signature=... HTTP/1.1".
+fn parse_query(request_line: &str) -> Option<(String, String)> {
+    let target = request_line.strip_prefix("GET ")?.split(' ').next()?;
+    let query = target.strip_prefix("/test?")?;
+    let (mut file, mut signature) = (None, None);
+    for pair in query.split('&') {
+        match pair.split_once('=') {
+            Some(("file", value)) => file = Some(value.to_string()),
+            Some(("signature", value)) => signature = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some((file?, signature?))
+}
+
+/// Ask the server about `signature` for `file`, returning the HTTP status and how long the
+/// answer took.
+pub fn request(addr: SocketAddr, file: &str, signature: &[u8]) -> Result<(u16, Duration)> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    let start = Instant::now();
+    write!(
+        stream,
+        "GET /test?file={}&signature={} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        file,
+        hex::encode(signature),
+        addr
+    )?;
+    let mut status_line = String::new();
+    BufReader::new(&stream).read_line(&mut status_line)?;
+    let elapsed = start.elapsed();
+
+    let status = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or(anyhow!("bad status line: {:?}", status_line))?;
+    Ok((status, elapsed))
+}
+
+/// How hard the attacker listens before committing to a byte.
+#[derive(Debug, Clone, Copy)]
+pub struct TimingAttack {
+    /// Timings per candidate byte in the first pass
+    pub samples: usize,
+    /// How many of the slowest candidates get measured again, `samples` times over, before one
+    /// is picked
+    pub finalists: usize,
+}
+
+impl Default for TimingAttack {
+    fn default() -> Self {
+        TimingAttack {
+            samples: 5,
+            finalists: 4,
+        }
+    }
+}
+
+impl TimingAttack {
+    /// Recover a `mac_len`-byte signature for `file` one byte at a time. Each byte goes to the
+    /// candidate with the slowest median response; the last byte doesn't leak through timing, so
+    /// it's brute forced on the status code. Returns None if no final byte was accepted, which
+    /// means an earlier guess was wrong.
+    pub fn recover_signature(
+        &self,
+        addr: SocketAddr,
+        file: &str,
+        mac_len: usize,
+    ) -> Result<Option<Vec<u8>>> {
+        if mac_len == 0 {
+            return Err(anyhow!("signature must be at least one byte long"));
+        }
+        let mut signature = vec![0u8; mac_len];
+        for position in 0..mac_len - 1 {
+            signature[position] = self.leak_byte(addr, file, &mut signature.clone(), position)?;
+        }
+
+        for byte in 0..=255 {
+            signature[mac_len - 1] = byte;
+            if request(addr, file, &signature)?.0 == 200 {
+                return Ok(Some(signature));
+            }
+        }
+        Ok(None)
+    }
+
+    fn leak_byte(
+        &self,
+        addr: SocketAddr,
+        file: &str,
+        guess: &mut [u8],
+        position: usize,
+    ) -> Result<u8> {
+        let mut ranked = Vec::with_capacity(256);
+        for byte in 0..=255 {
+            guess[position] = byte;
+            ranked.push((median_time(addr, file, guess, self.samples)?, byte));
+        }
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+
+        // The first pass only has to get the right byte near the top; a closer look at the
+        // finalists settles it.
+        let mut best = (Duration::ZERO, ranked[0].1);
+        for &(_, byte) in ranked.iter().take(self.finalists.max(1)) {
+            guess[position] = byte;
+            let time = median_time(addr, file, guess, self.samples * self.finalists.max(1))?;
+            best = best.max((time, byte));
+        }
+        Ok(best.1)
+    }
+}
+
+fn median_time(addr: SocketAddr, file: &str, signature: &[u8], samples: usize) -> Result<Duration> {
+    let mut times = (0..samples.max(1))
+        .map(|_| request(addr, file, signature).map(|(_, time)| time))
+        .collect::<Result<Vec<_>>>()?;
+    times.sort_unstable();
+    Ok(times[times.len() / 2])
+}
+
+#[test]
+fn insecure_compare_test() {
+    let delay = Duration::from_millis(5);
+    let start = Instant::now();
+    assert!(!insecure_compare(b"abcd", b"xbcd", delay));
+    assert!(start.elapsed() < delay);
+
+    let start = Instant::now();
+    assert!(!insecure_compare(b"abcd", b"abcx", delay));
+    assert!(start.elapsed() >= delay * 3);
+
+    assert!(insecure_compare(b"abcd", b"abcd", Duration::ZERO));
+    assert!(!insecure_compare(b"abc", b"abcd", Duration::ZERO));
+}
+
+#[test]
+fn timing_leak_server_test() -> Result<()> {
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+    let server = TimingLeakServer::start(KEY, Duration::ZERO, Sha1::OUTPUT_SIZE)?;
+    let mac = sha1::hmac_sha1(KEY, b"foo");
+    assert_eq!(request(server.addr(), "foo", &mac)?.0, 200);
+    assert_eq!(request(server.addr(), "bar", &mac)?.0, 500);
+    assert_eq!(request(server.addr(), "foo", &mac[..19])?.0, 500);
+
+    for mac_len in [0, Sha1::OUTPUT_SIZE + 1] {
+        assert!(TimingLeakServer::start(KEY, Duration::ZERO, mac_len).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn challenge_31_test() -> Result<()> {
+    use crate::utils;
+    const FILE: &str = "foo";
+    const MAC_LEN: usize = 3;
+    let key = utils::generate_16_byte_key();
+    let server = TimingLeakServer::start(&key, Duration::from_millis(3), MAC_LEN)?;
+
+    let signature = TimingAttack::default().recover_signature(server.addr(), FILE, MAC_LEN)?;
+    let expected = sha1::hmac_sha1(&key, FILE.as_bytes());
+    utils::require_eq(signature.as_deref(), Some(&expected[..MAC_LEN]))
+}
+
+#[test]
+#[ignore = "recovers the whole 20-byte MAC, which takes the better part of an hour"]
+fn challenge_31_full_mac_test() -> Result<()> {
+    use crate::utils;
+    const FILE: &str = "foo";
+    let key = utils::generate_16_byte_key();
+    let server = TimingLeakServer::start(&key, Duration::from_millis(5), Sha1::OUTPUT_SIZE)?;
+
+    let signature =
+        TimingAttack::default().recover_signature(server.addr(), FILE, Sha1::OUTPUT_SIZE)?;
+    let expected = sha1::hmac_sha1(&key, FILE.as_bytes());
+    utils::require_eq(signature, Some(expected))
+}
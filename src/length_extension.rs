@@ -0,0 +1,143 @@
+use crate::{hash::MerkleDamgard, hmac};
+use rand::Rng;
+use std::{marker::PhantomData, ops::RangeInclusive};
+
+/// Server holding a secret key and authenticating messages as H(key || message)
+pub struct SecretPrefixMac<H> {
+    key: Vec<u8>,
+    hash: PhantomData<H>,
+}
+
+impl<H: MerkleDamgard> SecretPrefixMac<H> {
+    pub fn new(key: &[u8]) -> Self {
+        SecretPrefixMac {
+            key: key.to_vec(),
+            hash: PhantomData,
+        }
+    }
+
+    /// A server with a random key somewhere between 1 and 64 bytes long
+    pub fn random() -> Self {
+        let mut rng = rand::rng();
+        let key: Vec<u8> = (0..rng.random_range(1..=64))
+            .map(|_| rng.random())
+            .collect();
+        Self::new(&key)
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        let mut hash = H::new();
+        hash.update(&self.key);
+        hash.update(message);
+        hash.finalize()
+    }
+
+    pub fn verify(&self, message: &[u8], mac: &[u8]) -> bool {
+        hmac::constant_time_eq(&self.sign(message), mac)
+    }
+}
+
+/// A message and MAC that verify under the server's key, plus the key length that got us there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forgery {
+    pub key_len: usize,
+    pub message: Vec<u8>,
+    pub mac: Vec<u8>,
+}
+
+/// Extend `message` (authenticated by `mac`) with `extension`, assuming a key of `key_len` bytes.
+/// Returns `message || glue padding || extension` and its MAC, or None if `mac` isn't a digest
+/// of the right size.
+pub fn extend<H: MerkleDamgard>(
+    mac: &[u8],
+    message: &[u8],
+    extension: &[u8],
+    key_len: usize,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    let signed_len = (key_len + message.len()) as u64;
+    let glue = H::padding(signed_len);
+
+    let mut hash = H::from_digest(mac, signed_len + glue.len() as u64)?;
+    hash.update(extension);
+
+    let mut forged = message.to_vec();
+    forged.extend(glue);
+    forged.extend_from_slice(extension);
+    Some((forged, hash.finalize()))
+}
+
+/// Try every key length in `key_lens` until `verify` accepts the extended message.
+pub fn forge<H, F>(
+    mac: &[u8],
+    message: &[u8],
+    extension: &[u8],
+    key_lens: RangeInclusive<usize>,
+    mut verify: F,
+) -> Option<Forgery>
+where
+    H: MerkleDamgard,
+    F: FnMut(&[u8], &[u8]) -> bool,
+{
+    key_lens.into_iter().find_map(|key_len| {
+        let (message, mac) = extend::<H>(mac, message, extension, key_len)?;
+        verify(&message, &mac).then_some(Forgery {
+            key_len,
+            message,
+            mac,
+        })
+    })
+}
+
+#[cfg(test)]
+const MESSAGE: &[u8] =
+    b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+#[cfg(test)]
+const EXTENSION: &[u8] = b";admin=true";
+
+#[cfg(test)]
+fn forge_admin<H: MerkleDamgard>() -> Option<Forgery> {
+    let server = SecretPrefixMac::<H>::random();
+    let mac = server.sign(MESSAGE);
+    let forgery = forge::<H, _>(&mac, MESSAGE, EXTENSION, 0..=64, |message, mac| {
+        server.verify(message, mac)
+    })?;
+    assert!(server.verify(&forgery.message, &forgery.mac));
+    assert!(forgery.message.starts_with(MESSAGE));
+    assert!(forgery.message.ends_with(EXTENSION));
+    Some(forgery)
+}
+
+#[test]
+fn challenge_29_test() {
+    use crate::sha1::Sha1;
+    assert!(forge_admin::<Sha1>().is_some());
+}
+
+#[test]
+fn challenge_30_test() {
+    use crate::md4::Md4;
+    assert!(forge_admin::<Md4>().is_some());
+}
+
+#[test]
+fn sha256_length_extension_test() {
+    use crate::sha256::Sha256;
+    assert!(forge_admin::<Sha256>().is_some());
+}
+
+#[test]
+fn length_extension_key_len_test() {
+    use crate::sha1::Sha1;
+    let server = SecretPrefixMac::<Sha1>::new(b"YELLOW SUBMARINE");
+    let mac = server.sign(MESSAGE);
+    let verify = |message: &[u8], mac: &[u8]| server.verify(message, mac);
+
+    let forgery = forge::<Sha1, _>(&mac, MESSAGE, EXTENSION, 0..=64, verify).unwrap();
+    assert_eq!(forgery.key_len, 16);
+    // Guessing the wrong range of key lengths gets us nowhere.
+    assert_eq!(
+        forge::<Sha1, _>(&mac, MESSAGE, EXTENSION, 0..=15, verify),
+        None
+    );
+    assert_eq!(extend::<Sha1>(&mac[1..], MESSAGE, EXTENSION, 16), None);
+}
@@ -3,9 +3,35 @@ use base64::{Engine, engine as _, engine::general_purpose::STANDARD};
 use rand::Rng;
 use std::collections::HashMap;
 mod aes_128;
+mod bignum;
+mod bitflip;
 mod challenge_16;
+mod challenge_26;
+mod challenge_27;
+mod channel;
+mod ctr_edit;
+mod dh;
+mod dh_mitm;
+mod fixed_nonce_ctr;
+mod hash;
+mod hmac;
+mod hmac_timing;
+mod length_extension;
+mod md4;
+mod md5;
+mod mt19937;
+mod mt19937_cipher;
+mod mt19937_clone;
+mod mt19937_seed;
+mod padding_oracle;
 mod repeated_xor;
+mod rsa;
+mod rsa_broadcast;
+mod sha1;
+mod sha256;
 mod single_byte_xor;
+mod srp;
+mod srp_simple;
 mod traits;
 mod types;
 mod utils;
@@ -479,11 +505,10 @@ fn challenge_14() -> Result<(), anyhow::Error> {
         // Encrypt with the prefix + our starting point + our blocksize for the initial length.
         let initial_len = challenge_14_oracle(prefix, &[b'A'; 2], &[b'A'; 16], key).len();
         (2..64)
-            .find_map(|x| {
+            .find(|&x| {
                 let input_bytes = vec![b'A'; x];
                 let current_len = challenge_14_oracle(prefix, &[b'A'; 16], &input_bytes, key).len();
-                let diff = current_len - initial_len;
-                if diff > 1 { Some(x) } else { None }
+                current_len - initial_len > 1
             })
             .unwrap_or(0) // Or handle the case where no block size change is detected differently
     }
@@ -0,0 +1,99 @@
+// MD4 (RFC 1320). Like the SHA modules, the chaining state is public so it can be resumed from a
+// digest or driven block by block.
+
+use crate::hash::{Compression, Endian, MdHash};
+
+pub const INITIAL_STATE: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+
+/// Split a 64-byte block into the sixteen little-endian words the rounds work on
+pub fn message_words(block: &[u8]) -> [u32; 16] {
+    let mut x = [0u32; 16];
+    for (i, word) in block.chunks(4).take(16).enumerate() {
+        x[i] = Endian::Little.read_word(word);
+    }
+    x
+}
+
+/// MD4's registers a, b, c, d and its compression function
+#[derive(Clone, Debug)]
+pub struct Md4Compression;
+
+pub type Md4 = MdHash<Md4Compression>;
+
+impl Compression for Md4Compression {
+    type State = [u32; 4];
+    const INITIAL_STATE: [u32; 4] = INITIAL_STATE;
+    const ENDIAN: Endian = Endian::Little;
+
+    fn compress(state: &mut [u32; 4], block: &[u8]) {
+        let x = message_words(block);
+        let [mut a, mut b, mut c, mut d] = *state;
+
+        let f = |x: u32, y: u32, z: u32| (x & y) | (!x & z);
+        let g = |x: u32, y: u32, z: u32| (x & y) | (x & z) | (y & z);
+        let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+        // Each round walks the words in its own order, rotating the registers between steps.
+        for i in 0..16 {
+            let k = i;
+            let s = [3, 7, 11, 19][i % 4];
+            let t = a.wrapping_add(f(b, c, d)).wrapping_add(x[k]).rotate_left(s);
+            (a, b, c, d) = (d, t, b, c);
+        }
+        for i in 0..16 {
+            let k = (i % 4) * 4 + i / 4;
+            let s = [3, 5, 9, 13][i % 4];
+            let t = a
+                .wrapping_add(g(b, c, d))
+                .wrapping_add(x[k])
+                .wrapping_add(0x5a82_7999)
+                .rotate_left(s);
+            (a, b, c, d) = (d, t, b, c);
+        }
+        for i in 0..16 {
+            let k = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15][i];
+            let s = [3, 9, 11, 15][i % 4];
+            let t = a
+                .wrapping_add(h(b, c, d))
+                .wrapping_add(x[k])
+                .wrapping_add(0x6ed9_eba1)
+                .rotate_left(s);
+            (a, b, c, d) = (d, t, b, c);
+        }
+
+        for (register, value) in state.iter_mut().zip([a, b, c, d]) {
+            *register = register.wrapping_add(value);
+        }
+    }
+}
+
+#[test]
+fn md4_rfc_1320_test() {
+    use crate::{hash::Hash, traits::BytesHexExt};
+    const VECTORS: [(&str, &str); 7] = [
+        ("", "31d6cfe0d16ae931b73c59d7e0c089c0"),
+        ("a", "bde52cb31de33e46245e05fbdbd6fb24"),
+        ("abc", "a448017aaf21d8525fc10ae87aa6729d"),
+        ("message digest", "d9130a8164549fe818874806e1c7014b"),
+        (
+            "abcdefghijklmnopqrstuvwxyz",
+            "d79e1c308aa5bbcdeea8ed63df412da9",
+        ),
+        (
+            "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+            "043f8582f241db351ce627e153e7f0e4",
+        ),
+        (
+            "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+            "e33b4ddc9c38f2199c3e7b164fcc0536",
+        ),
+    ];
+    for (input, expected) in VECTORS {
+        assert_eq!(Md4::digest(input.as_bytes()).to_hex(), expected);
+    }
+
+    // Same answer when fed a byte at a time.
+    let mut md4 = Md4::new();
+    VECTORS[6].0.bytes().for_each(|byte| md4.update(&[byte]));
+    assert_eq!(md4.finalize().to_hex(), VECTORS[6].1);
+}
@@ -0,0 +1,307 @@
+use rand::RngCore;
+
+pub const N: usize = 624;
+pub const M: usize = 397;
+pub const MATRIX_A: u32 = 0x9908_b0df;
+const UPPER_MASK: u32 = 0x8000_0000;
+const LOWER_MASK: u32 = 0x7fff_ffff;
+
+const N_64: usize = 312;
+const M_64: usize = 156;
+const MATRIX_A_64: u64 = 0xb502_6f5a_a966_19e9;
+const UPPER_MASK_64: u64 = 0xffff_ffff_8000_0000;
+const LOWER_MASK_64: u64 = 0x7fff_ffff;
+
+/// Seed used by the reference implementation when none is given
+pub const DEFAULT_SEED: u32 = 5489;
+
+/// 32-bit Mersenne Twister, following the 2002 reference implementation
+#[derive(Clone, Debug)]
+pub struct Mt19937 {
+    state: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    /// Seed from a single integer (`init_genrand`)
+    pub fn new(seed: u32) -> Self {
+        let mut state = [0u32; N];
+        state[0] = seed;
+        for i in 1..N {
+            state[i] = 1_812_433_253u32
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        Mt19937 { state, index: N }
+    }
+
+    /// Seed from an array of integers (`init_by_array`)
+    pub fn from_array(key: &[u32]) -> Self {
+        let mut mt = Mt19937::new(19_650_218);
+        let state = &mut mt.state;
+        let (mut i, mut j) = (1, 0);
+        for _ in 0..N.max(key.len()) {
+            state[i] = (state[i] ^ (state[i - 1] ^ (state[i - 1] >> 30)).wrapping_mul(1_664_525))
+                .wrapping_add(key.get(j).copied().unwrap_or_default())
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N - 1 {
+            state[i] = (state[i]
+                ^ (state[i - 1] ^ (state[i - 1] >> 30)).wrapping_mul(1_566_083_941))
+            .wrapping_sub(i as u32);
+            i += 1;
+            if i >= N {
+                state[0] = state[N - 1];
+                i = 1;
+            }
+        }
+        // MSB is 1, assuring a non-zero initial state.
+        state[0] = UPPER_MASK;
+        mt
+    }
+
+    /// Rebuild a generator from its raw state words. `index` is the position of the next word to
+    /// hand out; `N` means the state gets twisted first.
+    pub fn from_state(state: [u32; N], index: usize) -> Self {
+        Mt19937 {
+            state,
+            index: index.min(N),
+        }
+    }
+
+    /// Next 32-bit output (`genrand_int32`)
+    pub fn extract_number(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+        let y = self.state[self.index];
+        self.index += 1;
+        temper(y)
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            let y = (self.state[i] & UPPER_MASK) | (self.state[(i + 1) % N] & LOWER_MASK);
+            let mag = if y & 1 == 0 { 0 } else { MATRIX_A };
+            self.state[i] = self.state[(i + M) % N] ^ (y >> 1) ^ mag;
+        }
+        self.index = 0;
+    }
+}
+
+/// Output transform applied to every state word before it's handed out
+pub fn temper(mut y: u32) -> u32 {
+    y ^= y >> 11;
+    y ^= (y << 7) & 0x9d2c_5680;
+    y ^= (y << 15) & 0xefc6_0000;
+    y ^ (y >> 18)
+}
+
+/// Invert `temper`, recovering the state word behind an output
+pub fn untemper(mut y: u32) -> u32 {
+    y ^= y >> 18;
+    y ^= (y << 15) & 0xefc6_0000;
+    // The left shift by 7 only reaches 7 new bits per round, so it takes a few to settle.
+    let mut x = y;
+    for _ in 0..4 {
+        x = y ^ ((x << 7) & 0x9d2c_5680);
+    }
+    // Same for the right shift by 11.
+    let mut z = x;
+    for _ in 0..2 {
+        z = x ^ (z >> 11);
+    }
+    z
+}
+
+impl RngCore for Mt19937 {
+    fn next_u32(&mut self) -> u32 {
+        self.extract_number()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.extract_number() as u64;
+        let high = self.extract_number() as u64;
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.chunks_mut(4).for_each(|chunk| {
+            let bytes = self.extract_number().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        });
+    }
+}
+
+/// 64-bit Mersenne Twister, following the 2004 reference implementation
+#[derive(Clone, Debug)]
+pub struct Mt19937_64 {
+    state: [u64; N_64],
+    index: usize,
+}
+
+impl Mt19937_64 {
+    /// Seed from a single integer (`init_genrand64`)
+    pub fn new(seed: u64) -> Self {
+        let mut state = [0u64; N_64];
+        state[0] = seed;
+        for i in 1..N_64 {
+            state[i] = 6_364_136_223_846_793_005u64
+                .wrapping_mul(state[i - 1] ^ (state[i - 1] >> 62))
+                .wrapping_add(i as u64);
+        }
+        Mt19937_64 { state, index: N_64 }
+    }
+
+    /// Seed from an array of integers (`init_by_array64`)
+    pub fn from_array(key: &[u64]) -> Self {
+        let mut mt = Mt19937_64::new(19_650_218);
+        let state = &mut mt.state;
+        let (mut i, mut j) = (1, 0);
+        for _ in 0..N_64.max(key.len()) {
+            state[i] = (state[i]
+                ^ (state[i - 1] ^ (state[i - 1] >> 62)).wrapping_mul(3_935_559_000_370_003_845))
+            .wrapping_add(key.get(j).copied().unwrap_or_default())
+            .wrapping_add(j as u64);
+            i += 1;
+            j += 1;
+            if i >= N_64 {
+                state[0] = state[N_64 - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N_64 - 1 {
+            state[i] = (state[i]
+                ^ (state[i - 1] ^ (state[i - 1] >> 62)).wrapping_mul(2_862_933_555_777_941_757))
+            .wrapping_sub(i as u64);
+            i += 1;
+            if i >= N_64 {
+                state[0] = state[N_64 - 1];
+                i = 1;
+            }
+        }
+        state[0] = 1 << 63;
+        mt
+    }
+
+    /// Next 64-bit output (`genrand64_int64`)
+    pub fn extract_number(&mut self) -> u64 {
+        if self.index >= N_64 {
+            self.twist();
+        }
+        let mut y = self.state[self.index];
+        self.index += 1;
+        y ^= (y >> 29) & 0x5555_5555_5555_5555;
+        y ^= (y << 17) & 0x71d6_7fff_eda6_0000;
+        y ^= (y << 37) & 0xfff7_eee0_0000_0000;
+        y ^ (y >> 43)
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N_64 {
+            let y = (self.state[i] & UPPER_MASK_64) | (self.state[(i + 1) % N_64] & LOWER_MASK_64);
+            let mag = if y & 1 == 0 { 0 } else { MATRIX_A_64 };
+            self.state[i] = self.state[(i + M_64) % N_64] ^ (y >> 1) ^ mag;
+        }
+        self.index = 0;
+    }
+}
+
+impl RngCore for Mt19937_64 {
+    fn next_u32(&mut self) -> u32 {
+        self.extract_number() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.extract_number()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.chunks_mut(8).for_each(|chunk| {
+            let bytes = self.extract_number().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        });
+    }
+}
+
+#[test]
+fn challenge_21_test() {
+    // Reference output of init_by_array({0x123, 0x234, 0x345, 0x456}).
+    const EXPECTED_ARRAY: [u32; 5] = [1067595299, 955945823, 477289528, 4107218783, 4228976476];
+    let mut mt = Mt19937::from_array(&[0x123, 0x234, 0x345, 0x456]);
+    let outputs: Vec<u32> = (0..5).map(|_| mt.extract_number()).collect();
+    assert_eq!(outputs, EXPECTED_ARRAY);
+
+    const EXPECTED_DEFAULT: [u32; 5] = [3499211612, 581869302, 3890346734, 3586334585, 545404204];
+    let mut mt = Mt19937::new(DEFAULT_SEED);
+    let outputs: Vec<u32> = (0..5).map(|_| mt.extract_number()).collect();
+    assert_eq!(outputs, EXPECTED_DEFAULT);
+
+    // The C++ standard requires the 10000th output of a default constructed std::mt19937.
+    let mut mt = Mt19937::new(DEFAULT_SEED);
+    let outputs: Vec<u32> = (0..10000).map(|_| mt.extract_number()).collect();
+    assert_eq!(outputs[9999], 4123659995);
+}
+
+#[test]
+fn mt19937_64_test() {
+    // Reference output of init_by_array64({0x12345, 0x23456, 0x34567, 0x45678}).
+    const EXPECTED_ARRAY: [u64; 5] = [
+        7266447313870364031,
+        4946485549665804864,
+        16945909448695747420,
+        16394063075524226720,
+        4873882236456199058,
+    ];
+    let mut mt = Mt19937_64::from_array(&[0x12345, 0x23456, 0x34567, 0x45678]);
+    let outputs: Vec<u64> = (0..5).map(|_| mt.extract_number()).collect();
+    assert_eq!(outputs, EXPECTED_ARRAY);
+
+    // The C++ standard requires the 10000th output of a default constructed std::mt19937_64.
+    let mut mt = Mt19937_64::new(DEFAULT_SEED as u64);
+    let outputs: Vec<u64> = (0..10000).map(|_| mt.extract_number()).collect();
+    assert_eq!(outputs[9999], 9981545732273789042);
+}
+
+#[test]
+fn mt19937_byte_source_test() {
+    use crate::utils;
+    // Seeded generators hand out the same keys, so oracles built on them are reproducible.
+    let key = utils::generate_16_byte_key_from(&mut Mt19937::new(1234));
+    assert_eq!(
+        key,
+        utils::generate_16_byte_key_from(&mut Mt19937::new(1234))
+    );
+    assert_ne!(
+        key,
+        utils::generate_16_byte_key_from(&mut Mt19937::new(1235))
+    );
+    assert!(key.iter().all(|byte| (33..126).contains(byte)));
+
+    let mut bytes = [0u8; 6];
+    Mt19937::new(DEFAULT_SEED).fill_bytes(&mut bytes);
+    assert_eq!(bytes[..4], 3499211612u32.to_le_bytes());
+    assert_eq!(bytes[4..], 581869302u32.to_le_bytes()[..2]);
+}
+
+#[test]
+fn untemper_test() {
+    let mut mt = Mt19937::new(rand::random());
+    for _ in 0..10000 {
+        let y = mt.extract_number();
+        assert_eq!(temper(untemper(y)), y);
+        assert_eq!(untemper(temper(y)), y);
+    }
+}
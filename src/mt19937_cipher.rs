@@ -0,0 +1,90 @@
+use crate::{
+    mt19937::Mt19937,
+    mt19937_seed::{self, Clock},
+    utils,
+};
+
+/// Keystream made of the low byte of every output of an MT19937 seeded with `seed`
+pub fn keystream(seed: u32, length: usize) -> Vec<u8> {
+    let mut mt = Mt19937::new(seed);
+    (0..length).map(|_| mt.extract_number() as u8).collect()
+}
+
+/// Encrypt with the MT19937 stream cipher from Cryptopals 24 (16-bit key)
+pub fn encrypt_mt19937(data: &[u8], key: u16) -> Vec<u8> {
+    utils::bytes_xor(data, &keystream(key as u32, data.len()))
+}
+
+/// Decrypt with the MT19937 stream cipher from Cryptopals 24 (16-bit key)
+pub fn decrypt_mt19937(data: &[u8], key: u16) -> Vec<u8> {
+    encrypt_mt19937(data, key)
+}
+
+/// Brute force the 16-bit seed of an MT19937 stream cipher, knowing the plaintext ends in
+/// `known_suffix`. An empty suffix pins nothing down, so it finds nothing.
+pub fn recover_16bit_seed(ciphertext: &[u8], known_suffix: &[u8]) -> Option<u16> {
+    if known_suffix.is_empty() || known_suffix.len() > ciphertext.len() {
+        return None;
+    }
+    let offset = ciphertext.len() - known_suffix.len();
+    let target = utils::bytes_xor(&ciphertext[offset..], known_suffix);
+    (0..=u16::MAX).find(|&seed| keystream(seed as u32, ciphertext.len())[offset..] == target[..])
+}
+
+/// Password reset token taken straight from an MT19937 seeded with the current time
+pub fn generate_reset_token<C: Clock + ?Sized>(clock: &C, length: usize) -> Vec<u8> {
+    keystream(clock.now() as u32, length)
+}
+
+/// Check whether `token` came out of an MT19937 seeded with a timestamp from the last `window`
+/// seconds, returning that seed if it did. An empty token matches every seed, so it's rejected.
+pub fn detect_time_seeded_token<C: Clock + ?Sized>(
+    token: &[u8],
+    clock: &C,
+    window: u64,
+) -> Option<u32> {
+    if token.is_empty() {
+        return None;
+    }
+    mt19937_seed::find_timestamp_seed(clock, window, |seed| keystream(seed, token.len()) == token)
+}
+
+#[test]
+fn challenge_24_test() {
+    const PLAINTEXT: &[u8] = b"the quick brown fox jumps over the lazy dog";
+    let key: u16 = rand::random();
+    let encrypted = encrypt_mt19937(PLAINTEXT, key);
+    assert_ne!(encrypted.as_slice(), PLAINTEXT);
+    assert_eq!(decrypt_mt19937(&encrypted, key), PLAINTEXT);
+    assert_ne!(decrypt_mt19937(&encrypted, key.wrapping_add(1)), PLAINTEXT);
+}
+
+#[test]
+fn reset_token_detection_test() {
+    use crate::mt19937_seed::ManualClock;
+    const WINDOW: u64 = 600;
+    let clock = ManualClock::new(1_700_000_000);
+    let token = generate_reset_token(&clock, 16);
+    let seed = clock.now() as u32;
+    clock.sleep(90);
+
+    assert_eq!(detect_time_seeded_token(&token, &clock, WINDOW), Some(seed));
+    let random_token: Vec<u8> = (0..16).map(|_| rand::random()).collect();
+    assert_eq!(
+        detect_time_seeded_token(&random_token, &clock, WINDOW),
+        None
+    );
+    assert_eq!(detect_time_seeded_token(&[], &clock, WINDOW), None);
+}
+
+#[test]
+fn challenge_24_seed_test() {
+    let seed: u16 = rand::random();
+    let mut plaintext = utils::random_byte_vec();
+    plaintext.extend_from_slice(&[b'A'; 14]);
+    let ciphertext = encrypt_mt19937(&plaintext, seed);
+
+    assert_eq!(recover_16bit_seed(&ciphertext, &[b'A'; 14]), Some(seed));
+    assert_eq!(recover_16bit_seed(&ciphertext[..4], &[b'A'; 14]), None);
+    assert_eq!(recover_16bit_seed(&ciphertext, &[]), None);
+}
@@ -0,0 +1,344 @@
+use crate::mt19937::{self, MATRIX_A, Mt19937, N};
+use anyhow::{Result, anyhow};
+
+// Bits of state that influence future outputs: all 624 words, except that only the top bit of
+// the oldest word is ever used again.
+const STATE_BITS: usize = N * 32 - 31;
+
+/// Clone a generator from 624 (or more) consecutive outputs. The clone picks up right after the
+/// last output given, so it predicts everything the original produces from there on.
+pub fn clone_from_outputs(outputs: &[u32]) -> Result<Mt19937> {
+    if outputs.len() < N {
+        return Err(anyhow!(
+            "need {} outputs to clone MT19937, got {}",
+            N,
+            outputs.len()
+        ));
+    }
+    let mut state = [0u32; N];
+    state
+        .iter_mut()
+        .zip(outputs)
+        .for_each(|(word, &output)| *word = mt19937::untemper(output));
+    let mut clone = Mt19937::from_state(state, N);
+
+    // Catch up with the rest of the outputs, which doubles as a check that they belong together.
+    for (i, &output) in outputs.iter().enumerate().skip(N) {
+        if clone.extract_number() != output {
+            return Err(anyhow!(
+                "output {} doesn't follow from the ones before it",
+                i
+            ));
+        }
+    }
+    Ok(clone)
+}
+
+/// Number of consecutive outputs needed to clone a generator when only the bits in `mask` of
+/// every output can be seen, or `None` when nothing can be seen at all. Which equations end up
+/// independent depends on where the visible bits sit, so we run the elimination and count.
+pub fn samples_required(mask: u32) -> Option<usize> {
+    match mask {
+        0 => None,
+        u32::MAX => Some(N),
+        _ => {
+            // Only the rank matters here, and that doesn't depend on the observed values.
+            let mut cloner = PartialCloner::new(mask);
+            (1..).find(|_| cloner.push(0).is_ok_and(|_| cloner.is_determined()))
+        }
+    }
+}
+
+/// Clone a generator from consecutive outputs of which only the bits in `mask` are known. Every
+/// output bit is a linear function (over GF(2)) of the state bits, so with enough outputs the
+/// state falls out of a system of linear equations. See `samples_required` for how many.
+pub fn clone_from_partial_outputs(outputs: &[u32], mask: u32) -> Result<Mt19937> {
+    if mask == u32::MAX {
+        return clone_from_outputs(outputs);
+    }
+    if mask == 0 {
+        return Err(anyhow!("mask hides every output bit"));
+    }
+
+    let mut cloner = PartialCloner::new(mask);
+    for &output in outputs {
+        cloner.push(output)?;
+        if cloner.is_determined() {
+            break;
+        }
+    }
+    if !cloner.is_determined() {
+        return Err(anyhow!(
+            "outputs only pin down {} of {} state bits",
+            cloner.system.rank,
+            STATE_BITS
+        ));
+    }
+
+    let mut clone = Mt19937::from_state(cloner.system.solve(), 0);
+    // The first output depends on bits we never solved for, so there's nothing to check there.
+    clone.extract_number();
+    for (i, &output) in outputs.iter().enumerate().skip(1) {
+        if clone.extract_number() & mask != output & mask {
+            return Err(anyhow!("recovered state doesn't reproduce output {}", i));
+        }
+    }
+    Ok(clone)
+}
+
+/// Clone a generator whose outputs were truncated to their lowest byte.
+pub fn clone_from_low_bytes(bytes: &[u8]) -> Result<Mt19937> {
+    let outputs: Vec<u32> = bytes.iter().map(|&byte| byte as u32).collect();
+    clone_from_partial_outputs(&outputs, 0xff)
+}
+
+// Turns every visible bit of every output into an equation over the initial state bits.
+struct PartialCloner {
+    mask: u32,
+    temper_rows: [u32; 32],
+    symbolic: SymbolicState,
+    system: Gf2System,
+    seen: usize,
+}
+
+impl PartialCloner {
+    fn new(mask: u32) -> Self {
+        PartialCloner {
+            mask,
+            temper_rows: temper_rows(),
+            symbolic: SymbolicState::new(),
+            system: Gf2System::new(),
+            seen: 0,
+        }
+    }
+
+    fn push(&mut self, output: u32) -> Result<()> {
+        let word = self.symbolic.word(self.seen);
+        self.seen += 1;
+        // The low 31 bits of the first word never come back, so we leave its output out. That
+        // way they stay out of the system entirely, and full rank means everything that matters
+        // for future outputs is pinned down.
+        if self.seen == 1 {
+            return Ok(());
+        }
+        for bit in (0..32).filter(|bit| self.mask >> bit & 1 == 1) {
+            let mut row = zero_row();
+            (0..32)
+                .filter(|&input| self.temper_rows[bit] >> input & 1 == 1)
+                .for_each(|input| xor_row(&mut row, &word[input]));
+            self.system.insert(row, output >> bit & 1 == 1)?;
+        }
+        Ok(())
+    }
+
+    fn is_determined(&self) -> bool {
+        self.system.rank >= STATE_BITS
+    }
+}
+
+// A linear combination of the unknown state bits, one bit per unknown.
+const ROW_WORDS: usize = N * 32 / 64;
+type Row = Box<[u64; ROW_WORDS]>;
+
+fn zero_row() -> Row {
+    Box::new([0; ROW_WORDS])
+}
+
+fn unit_row(bit: usize) -> Row {
+    let mut row = zero_row();
+    row[bit / 64] |= 1 << (bit % 64);
+    row
+}
+
+fn xor_row(left: &mut Row, right: &Row) {
+    left.iter_mut().zip(right.iter()).for_each(|(l, r)| *l ^= r);
+}
+
+// For every output bit, the state bits that temper() xors together to produce it.
+fn temper_rows() -> [u32; 32] {
+    let mut rows = [0u32; 32];
+    for input in 0..32 {
+        let column = mt19937::temper(1 << input);
+        for (output, row) in rows.iter_mut().enumerate() {
+            *row |= (column >> output & 1) << input;
+        }
+    }
+    rows
+}
+
+// The MT19937 word sequence, with every bit expressed in terms of the first 624 words. Words
+// are generated in place the same way twist() does it.
+struct SymbolicState {
+    words: Vec<Vec<Row>>,
+    generated: usize,
+}
+
+impl SymbolicState {
+    fn new() -> Self {
+        SymbolicState {
+            words: (0..N)
+                .map(|word| (0..32).map(|bit| unit_row(word * 32 + bit)).collect())
+                .collect(),
+            generated: N,
+        }
+    }
+
+    // Word `n` of the sequence. Words have to be requested in order.
+    fn word(&mut self, n: usize) -> &[Row] {
+        while self.generated <= n {
+            self.next_word();
+        }
+        &self.words[n % N]
+    }
+
+    // x[n] = x[n - 227] ^ twist(upper bit of x[n - 624], lower bits of x[n - 623])
+    fn next_word(&mut self) {
+        let n = self.generated;
+        let upper = self.words[n % N][31].clone();
+        let lower = self.words[(n + 1) % N].clone();
+        let mut word = self.words[(n + mt19937::M) % N].clone();
+        for (bit, row) in word.iter_mut().enumerate() {
+            // (y >> 1) puts the top bit of the upper word at bit 30, the rest comes from below.
+            match bit {
+                30 => xor_row(row, &upper),
+                31 => {}
+                _ => xor_row(row, &lower[bit + 1]),
+            }
+            if MATRIX_A >> bit & 1 == 1 {
+                xor_row(row, &lower[0]);
+            }
+        }
+        self.words[n % N] = word;
+        self.generated += 1;
+    }
+}
+
+// Incremental Gaussian elimination. Each pivot row has its lowest set bit at its own index.
+struct Gf2System {
+    pivots: Vec<Option<(Row, bool)>>,
+    rank: usize,
+}
+
+impl Gf2System {
+    fn new() -> Self {
+        Gf2System {
+            pivots: (0..N * 32).map(|_| None).collect(),
+            rank: 0,
+        }
+    }
+
+    fn insert(&mut self, mut row: Row, mut value: bool) -> Result<()> {
+        let mut word = 0;
+        while word < ROW_WORDS {
+            if row[word] == 0 {
+                word += 1;
+                continue;
+            }
+            let bit = word * 64 + row[word].trailing_zeros() as usize;
+            match &self.pivots[bit] {
+                Some((pivot, pivot_value)) => {
+                    row[word..]
+                        .iter_mut()
+                        .zip(&pivot[word..])
+                        .for_each(|(l, r)| *l ^= r);
+                    value ^= pivot_value;
+                }
+                None => {
+                    self.pivots[bit] = Some((row, value));
+                    self.rank += 1;
+                    return Ok(());
+                }
+            }
+        }
+        // The equation was already implied by earlier ones, so it had better agree with them.
+        if value {
+            return Err(anyhow!("outputs are inconsistent with MT19937"));
+        }
+        Ok(())
+    }
+
+    // Back substitution, with every free bit set to 0.
+    fn solve(&self) -> [u32; N] {
+        let mut solution = zero_row();
+        for bit in (0..N * 32).rev() {
+            if let Some((row, value)) = &self.pivots[bit] {
+                let parity = row
+                    .iter()
+                    .zip(solution.iter())
+                    .map(|(r, s)| (r & s).count_ones())
+                    .sum::<u32>();
+                if value ^ (parity % 2 == 1) {
+                    solution[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+        let mut state = [0u32; N];
+        state.iter_mut().enumerate().for_each(|(i, word)| {
+            *word = (solution[i / 2] >> (32 * (i % 2))) as u32;
+        });
+        state
+    }
+}
+
+#[test]
+fn challenge_23_test() -> Result<()> {
+    let mut original = Mt19937::new(rand::random());
+    let outputs: Vec<u32> = (0..N).map(|_| original.extract_number()).collect();
+    let mut clone = clone_from_outputs(&outputs)?;
+    for _ in 0..2000 {
+        assert_eq!(clone.extract_number(), original.extract_number());
+    }
+
+    // Outputs don't need to line up with a twist, and extra outputs are fine.
+    let outputs: Vec<u32> = (0..N + 100).map(|_| original.extract_number()).collect();
+    let mut clone = clone_from_outputs(&outputs[37..])?;
+    for _ in 0..2000 {
+        assert_eq!(clone.extract_number(), original.extract_number());
+    }
+
+    assert!(clone_from_outputs(&outputs[..N - 1]).is_err());
+    Ok(())
+}
+
+#[test]
+fn clone_from_partial_outputs_test() -> Result<()> {
+    const MASK: u32 = 0xffff_0000;
+    let mut original = Mt19937::new(rand::random());
+    let required = samples_required(MASK).ok_or(anyhow!("no sample count"))?;
+    let outputs: Vec<u32> = (0..required)
+        .map(|_| original.extract_number() & MASK)
+        .collect();
+
+    let mut clone = clone_from_partial_outputs(&outputs, MASK)?;
+    for _ in 0..2000 {
+        assert_eq!(clone.extract_number(), original.extract_number());
+    }
+    assert!(clone_from_partial_outputs(&outputs[..required - 1], MASK).is_err());
+    Ok(())
+}
+
+#[test]
+fn clone_from_low_bytes_test() -> Result<()> {
+    let mut original = Mt19937::new(rand::random());
+    let required = samples_required(0xff).ok_or(anyhow!("no sample count"))?;
+    // Where the visible bits sit matters, not just how many there are: the top eight bits only
+    // take 2494 outputs.
+    assert_eq!(required, 3740);
+    let bytes: Vec<u8> = (0..required)
+        .map(|_| original.extract_number() as u8)
+        .collect();
+
+    let mut clone = clone_from_low_bytes(&bytes)?;
+    for _ in 0..2000 {
+        assert_eq!(clone.extract_number(), original.extract_number());
+    }
+    Ok(())
+}
+
+#[test]
+fn samples_required_test() {
+    assert_eq!(samples_required(0), None);
+    assert_eq!(samples_required(u32::MAX), Some(N));
+    assert_eq!(samples_required(0xffff), Some(1248));
+    assert_eq!(samples_required(0xff00_0000), Some(2494));
+}
@@ -0,0 +1,96 @@
+use crate::mt19937::Mt19937;
+use std::{
+    cell::Cell,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Source of the current Unix time. Attacks take one of these so tests can simulate seconds
+/// passing instead of actually waiting for them.
+pub trait Clock {
+    fn now(&self) -> u64;
+    fn sleep(&self, seconds: u64);
+}
+
+/// The real wall clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+
+    fn sleep(&self, seconds: u64) {
+        std::thread::sleep(Duration::from_secs(seconds));
+    }
+}
+
+/// A clock that only moves when somebody sleeps on it
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new(start: u64) -> Self {
+        ManualClock {
+            now: Cell::new(start),
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+
+    fn sleep(&self, seconds: u64) {
+        self.now.set(self.now.get() + seconds);
+    }
+}
+
+/// Find the seed of an MT19937 that was seeded with a Unix timestamp from the last `window`
+/// seconds, given its first output. Recent timestamps are tried first.
+pub fn recover_timestamp_seed<C: Clock + ?Sized>(
+    first_output: u32,
+    clock: &C,
+    window: u64,
+) -> Option<u32> {
+    find_timestamp_seed(clock, window, |seed| {
+        Mt19937::new(seed).extract_number() == first_output
+    })
+}
+
+/// Try every Unix timestamp from the last `window` seconds as a seed, most recent first, and
+/// return the first one `matches` accepts.
+pub fn find_timestamp_seed<C: Clock + ?Sized, F: FnMut(u32) -> bool>(
+    clock: &C,
+    window: u64,
+    mut matches: F,
+) -> Option<u32> {
+    let now = clock.now();
+    (now.saturating_sub(window)..=now)
+        .rev()
+        .map(|timestamp| timestamp as u32)
+        .find(|&seed| matches(seed))
+}
+
+#[test]
+fn challenge_22_test() {
+    use rand::Rng;
+    let clock = ManualClock::new(1_700_000_000);
+    let mut rng = rand::rng();
+
+    // Wait a random number of seconds, seed with the current time, wait some more and hand out
+    // the first output.
+    clock.sleep(rng.random_range(40..1000));
+    let seed = clock.now() as u32;
+    let output = Mt19937::new(seed).extract_number();
+    clock.sleep(rng.random_range(40..1000));
+
+    assert_eq!(recover_timestamp_seed(output, &clock, 2000), Some(seed));
+    // Outside the window there's nothing to find.
+    clock.sleep(5000);
+    assert_eq!(recover_timestamp_seed(output, &clock, 2000), None);
+}
@@ -0,0 +1,215 @@
+use crate::{
+    aes_128::{pad_pkcs7, unpad_pkcs7, verify_pkcs7},
+    utils,
+};
+use anyhow::{Result, anyhow};
+
+const BLOCK_SIZE: usize = 16;
+
+/// Anything that can answer "does `iv || ciphertext` decrypt to valid PKCS#7?"
+pub trait PaddingOracle {
+    fn is_valid(&mut self, iv: &[u8], ciphertext: &[u8]) -> bool;
+}
+
+impl<F> PaddingOracle for F
+where
+    F: FnMut(&[u8], &[u8]) -> bool,
+{
+    fn is_valid(&mut self, iv: &[u8], ciphertext: &[u8]) -> bool {
+        self(iv, ciphertext)
+    }
+}
+
+/// Plaintext recovered through a padding oracle, together with the number of oracle queries
+/// it took.
+#[derive(Debug)]
+pub struct OracleResult {
+    pub plaintext: Vec<u8>,
+    pub queries: usize,
+}
+
+// Wraps an oracle so every query gets counted.
+struct CountingOracle<'a, O: PaddingOracle + ?Sized> {
+    oracle: &'a mut O,
+    queries: usize,
+}
+
+impl<O: PaddingOracle + ?Sized> CountingOracle<'_, O> {
+    fn query(&mut self, iv: &[u8], block: &[u8]) -> bool {
+        self.queries += 1;
+        self.oracle.is_valid(iv, block)
+    }
+}
+
+/// Recover the block cipher decryption of a single ciphertext block (the value that gets xored
+/// with the previous block) by forging the IV in front of it, one byte at a time starting at the
+/// end.
+fn intermediate_block<O: PaddingOracle + ?Sized>(
+    oracle: &mut CountingOracle<O>,
+    block: &[u8],
+) -> Result<[u8; BLOCK_SIZE]> {
+    let mut forged = [0u8; BLOCK_SIZE];
+    let mut intermediate = [0u8; BLOCK_SIZE];
+
+    for pos in (0..BLOCK_SIZE).rev() {
+        let pad = (BLOCK_SIZE - pos) as u8;
+        // Make the bytes we already know decrypt to the padding value we're after.
+        for i in pos + 1..BLOCK_SIZE {
+            forged[i] = intermediate[i] ^ pad;
+        }
+        let guess = (0..=u8::MAX)
+            .find(|&guess| {
+                forged[pos] = guess;
+                if !oracle.query(&forged, block) {
+                    return false;
+                }
+                // On the last byte a hit might come from "0x02 0x02" (or longer) instead of
+                // "0x01". Changing the byte before it only breaks the padding in that case.
+                if pos == BLOCK_SIZE - 1 {
+                    forged[pos - 1] ^= 0xff;
+                    let confirmed = oracle.query(&forged, block);
+                    forged[pos - 1] ^= 0xff;
+                    return confirmed;
+                }
+                true
+            })
+            .ok_or(anyhow!("no valid padding found for byte {}", pos))?;
+        intermediate[pos] = guess ^ pad;
+    }
+    Ok(intermediate)
+}
+
+/// Decrypt an AES-128-CBC ciphertext using nothing but a padding oracle. The returned plaintext
+/// has its PKCS#7 padding removed.
+pub fn decrypt<O: PaddingOracle + ?Sized>(
+    oracle: &mut O,
+    iv: &[u8],
+    ciphertext: &[u8],
+) -> Result<OracleResult> {
+    if iv.len() != BLOCK_SIZE {
+        return Err(anyhow!("iv must be {} bytes", BLOCK_SIZE));
+    }
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(BLOCK_SIZE) {
+        return Err(anyhow!(
+            "ciphertext length must be a non-zero multiple of {}",
+            BLOCK_SIZE
+        ));
+    }
+
+    let mut counting = CountingOracle { oracle, queries: 0 };
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut previous = iv;
+    for block in ciphertext.chunks(BLOCK_SIZE) {
+        let intermediate = intermediate_block(&mut counting, block)?;
+        plaintext.extend(utils::bytes_xor(&intermediate, previous));
+        previous = block;
+    }
+
+    if !verify_pkcs7(&plaintext) {
+        return Err(anyhow!("recovered plaintext is not PKCS#7 padded"));
+    }
+    Ok(OracleResult {
+        plaintext: unpad_pkcs7(plaintext),
+        queries: counting.queries,
+    })
+}
+
+/// IV and ciphertext forged through a padding oracle, together with the number of oracle
+/// queries it took.
+#[derive(Debug)]
+pub struct ForgeResult {
+    pub iv: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub queries: usize,
+}
+
+/// Encrypt arbitrary plaintext under the oracle's key without knowing it (CBC-R). We start from a
+/// random final block and work backwards: once the intermediate of a block is known, the block in
+/// front of it is chosen so the two xor to the plaintext we want.
+pub fn encrypt<O: PaddingOracle + ?Sized>(oracle: &mut O, plaintext: &[u8]) -> Result<ForgeResult> {
+    let padded = pad_pkcs7(plaintext);
+    let mut counting = CountingOracle { oracle, queries: 0 };
+
+    let mut blocks = vec![rand::random::<[u8; BLOCK_SIZE]>().to_vec()];
+    for target in padded.chunks(BLOCK_SIZE).rev() {
+        let intermediate = intermediate_block(&mut counting, &blocks[blocks.len() - 1])?;
+        blocks.push(utils::bytes_xor(&intermediate, target));
+    }
+    // The last block we forged sits in front of everything else, so it becomes the IV.
+    blocks.reverse();
+    let iv = blocks.remove(0);
+
+    Ok(ForgeResult {
+        iv,
+        ciphertext: blocks.concat(),
+        queries: counting.queries,
+    })
+}
+
+#[test]
+fn challenge_17_test() -> Result<()> {
+    use crate::{aes_128, traits::BytesBase64Ext};
+    const STRINGS: [&str; 10] = [
+        "MDAwMDAwTm93IHRoYXQgdGhlIHBhcnR5IGlzIGp1bXBpbmc=",
+        "MDAwMDAxV2l0aCB0aGUgYmFzcyBraWNrZWQgaW4gYW5kIHRoZSBWZWdhJ3MgYXJlIHB1bXBpbic=",
+        "MDAwMDAyUXVpY2sgdG8gdGhlIHBvaW50LCB0byB0aGUgcG9pbnQsIG5vIGZha2luZw==",
+        "MDAwMDAzQ29va2luZyBNQydzIGxpa2UgYSBwb3VuZCBvZiBiYWNvbg==",
+        "MDAwMDA0QnVybmluZyAnZW0sIGlmIHlvdSBhaW4ndCBxdWljayBhbmQgbmltYmxl",
+        "MDAwMDA1SSBnbyBjcmF6eSB3aGVuIEkgaGVhciBhIGN5bWJhbA==",
+        "MDAwMDA2QW5kIGEgaGlnaCBoYXQgd2l0aCBhIHNvdXBlZCB1cCB0ZW1wbw==",
+        "MDAwMDA3SSdtIG9uIGEgcm9sbCwgaXQncyB0aW1lIHRvIGdvIHNvbG8=",
+        "MDAwMDA4b2xsaW4nIGluIG15IGZpdmUgcG9pbnQgb2g=",
+        "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
+    ];
+    let key = utils::generate_16_byte_key();
+    // The oracle decrypts with the secret key and only tells us if the padding checks out.
+    let mut oracle = |iv: &[u8], ciphertext: &[u8]| {
+        aes_128::decrypt_aes128_cbc_raw(ciphertext, &key, &mut iv.to_vec())
+            .map(|plaintext| verify_pkcs7(&plaintext))
+            .unwrap_or(false)
+    };
+
+    for string in STRINGS {
+        let expected = <Vec<u8>>::try_from_base64(string)?;
+        let iv = utils::generate_16_byte_key();
+        let ciphertext = aes_128::encrypt_aes128_cbc(&expected, &key, &mut iv.clone())?;
+
+        let result = decrypt(&mut oracle, &iv, &ciphertext)?;
+        utils::require_eq(&result.plaintext, &expected)?;
+        // Every byte costs at most 256 guesses, plus one confirmation per block.
+        utils::require(
+            result.queries <= ciphertext.len() * 256 + ciphertext.len() / BLOCK_SIZE * 256,
+            &format!("too many oracle queries: {}", result.queries),
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn padding_oracle_rejects_bad_lengths() {
+    let mut oracle = |_: &[u8], _: &[u8]| true;
+    assert!(decrypt(&mut oracle, &[0; 15], &[0; 16]).is_err());
+    assert!(decrypt(&mut oracle, &[0; 16], &[0; 17]).is_err());
+    assert!(decrypt(&mut oracle, &[0; 16], &[]).is_err());
+}
+
+#[test]
+fn padding_oracle_encrypt_test() -> Result<()> {
+    use crate::aes_128;
+    const PLAINTEXT: &[u8] =
+        b"comment1=cooking%20MCs;userdata=;admin=true;comment2=%20like%20a%20pound";
+    let key = utils::generate_16_byte_key();
+    let mut oracle = |iv: &[u8], ciphertext: &[u8]| {
+        aes_128::decrypt_aes128_cbc_raw(ciphertext, &key, &mut iv.to_vec())
+            .map(|plaintext| verify_pkcs7(&plaintext))
+            .unwrap_or(false)
+    };
+
+    let forged = encrypt(&mut oracle, PLAINTEXT)?;
+    utils::require_eq(forged.iv.len(), BLOCK_SIZE)?;
+    utils::require_eq(forged.ciphertext.len(), pad_pkcs7(PLAINTEXT).len())?;
+
+    // The victim decrypts our forgery with the real key and gets our plaintext back.
+    let decrypted = aes_128::decrypt_aes128_cbc(&forged.ciphertext, &key, &mut forged.iv.clone())?;
+    utils::require_eq(decrypted.as_slice(), PLAINTEXT)
+}
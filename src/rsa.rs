@@ -0,0 +1,253 @@
+// Textbook RSA (Cryptopals 39): no padding, just m^e mod n.
+
+use crate::{
+    bignum::{self, BigUintExt},
+    types::MyBytes,
+};
+use anyhow::{Result, anyhow};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::RngCore;
+
+pub const SMALL_E: u32 = 3;
+pub const STANDARD_E: u32 = 65537;
+
+const MILLER_RABIN_ROUNDS: usize = 40;
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+/// Miller–Rabin with `rounds` random bases, after a quick pass of trial division
+pub fn is_probable_prime<R: RngCore + ?Sized>(n: &BigUint, rounds: usize, rng: &mut R) -> bool {
+    for small in SMALL_PRIMES {
+        if *n == BigUint::from(small) {
+            return true;
+        }
+        if (n % small).is_zero() {
+            return false;
+        }
+    }
+    if *n < BigUint::from(2u32) {
+        return false;
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - 1u32;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+
+    'witness: for _ in 0..rounds {
+        let a = bignum::random_below(rng, &(n - 3u32)) + 2u32;
+        let mut x = bignum::modpow(&a, &d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = bignum::modpow(&x, &BigUint::from(2u32), n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// A random prime of exactly `bits` bits. The top two bits are set so that two of them multiply
+/// out to a full-size modulus.
+pub fn generate_prime<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    assert!(bits >= 8, "primes this small aren't worth generating");
+    let top = BigUint::one() << (bits - 1);
+    let bound = BigUint::one() << bits;
+    loop {
+        let candidate = bignum::random_below(rng, &bound)
+            | &top
+            | (BigUint::one() << (bits - 2))
+            | BigUint::one();
+        if is_probable_prime(&candidate, MILLER_RABIN_ROUNDS, rng) {
+            return candidate;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaPublicKey {
+    pub n: BigUint,
+    pub e: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaPrivateKey {
+    pub n: BigUint,
+    pub d: BigUint,
+    pub p: BigUint,
+    pub q: BigUint,
+    /// d mod (p - 1), d mod (q - 1) and q^-1 mod p, for CRT decryption
+    pub dp: BigUint,
+    pub dq: BigUint,
+    pub qinv: BigUint,
+}
+
+#[derive(Debug, Clone)]
+pub struct RsaKeyPair {
+    pub public: RsaPublicKey,
+    pub private: RsaPrivateKey,
+}
+
+impl RsaKeyPair {
+    /// Build a key pair from known primes. None if e isn't invertible mod φ(n) = (p - 1)(q - 1)
+    /// or the primes are equal.
+    pub fn from_primes(p: BigUint, q: BigUint, e: u32) -> Option<Self> {
+        if p == q {
+            return None;
+        }
+        let e = BigUint::from(e);
+        let p_minus_one = &p - 1u32;
+        let q_minus_one = &q - 1u32;
+        let d = bignum::invmod(&e, &(&p_minus_one * &q_minus_one))?;
+        let n = &p * &q;
+        let private = RsaPrivateKey {
+            dp: &d % &p_minus_one,
+            dq: &d % &q_minus_one,
+            qinv: bignum::invmod(&q, &p)?,
+            n: n.clone(),
+            d,
+            p,
+            q,
+        };
+        Some(RsaKeyPair {
+            public: RsaPublicKey { n, e },
+            private,
+        })
+    }
+
+    /// A fresh key pair with a `bits`-bit modulus and public exponent `e`
+    pub fn generate<R: RngCore + ?Sized>(bits: u64, e: u32, rng: &mut R) -> Result<Self> {
+        if bits < 16 {
+            return Err(anyhow!("{}-bit modulus is too small", bits));
+        }
+        if e < 3 || e.is_multiple_of(2) {
+            return Err(anyhow!("public exponent must be odd and at least 3"));
+        }
+        // Primes with p - 1 divisible by e make e non-invertible, so just draw again.
+        loop {
+            let p = generate_prime(bits / 2, rng);
+            let q = generate_prime(bits - bits / 2, rng);
+            if let Some(keypair) = Self::from_primes(p, q, e) {
+                return Ok(keypair);
+            }
+        }
+    }
+}
+
+impl RsaPublicKey {
+    pub fn encrypt(&self, message: &BigUint) -> Result<BigUint> {
+        if *message >= self.n {
+            return Err(anyhow!("message doesn't fit under the modulus"));
+        }
+        Ok(bignum::modpow(message, &self.e, &self.n))
+    }
+
+    /// Encrypt a byte string read as a big-endian integer. Leading zero bytes don't survive the
+    /// round trip, as with any textbook RSA.
+    pub fn encrypt_bytes(&self, message: &[u8]) -> Result<MyBytes> {
+        let message = BigUint::from_my_bytes(&MyBytes::from(message.to_vec()));
+        Ok(self.encrypt(&message)?.to_my_bytes())
+    }
+}
+
+impl RsaPrivateKey {
+    /// c^d mod n, the slow way
+    pub fn decrypt(&self, ciphertext: &BigUint) -> Result<BigUint> {
+        if *ciphertext >= self.n {
+            return Err(anyhow!("ciphertext doesn't fit under the modulus"));
+        }
+        Ok(bignum::modpow(ciphertext, &self.d, &self.n))
+    }
+
+    /// Decrypt mod p and mod q with the reduced exponents and recombine (Garner's formula)
+    pub fn decrypt_crt(&self, ciphertext: &BigUint) -> Result<BigUint> {
+        if *ciphertext >= self.n {
+            return Err(anyhow!("ciphertext doesn't fit under the modulus"));
+        }
+        let m1 = bignum::modpow(ciphertext, &self.dp, &self.p);
+        let m2 = bignum::modpow(ciphertext, &self.dq, &self.q);
+        // h = qinv * (m1 - m2) mod p, kept non-negative
+        let h = (&self.qinv * (&m1 + &self.p - (&m2 % &self.p))) % &self.p;
+        Ok(m2 + h * &self.q)
+    }
+
+    pub fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<MyBytes> {
+        let ciphertext = BigUint::from_my_bytes(&MyBytes::from(ciphertext.to_vec()));
+        Ok(self.decrypt_crt(&ciphertext)?.to_my_bytes())
+    }
+}
+
+#[test]
+fn miller_rabin_test() {
+    let mut rng = rand::rng();
+    for prime in [2u32, 3, 97, 101, 7919, 65537, 2_147_483_647] {
+        assert!(is_probable_prime(&BigUint::from(prime), 20, &mut rng));
+    }
+    // 561 and 41041 are Carmichael numbers, which fool the plain Fermat test.
+    for composite in [0u32, 1, 4, 561, 41041, 65535, 2_147_483_649] {
+        assert!(!is_probable_prime(&BigUint::from(composite), 20, &mut rng));
+    }
+    let prime = generate_prime(128, &mut rng);
+    assert_eq!(prime.bits(), 128);
+    assert!(
+        openssl::bn::BigNum::from_slice(&prime.to_bytes_be())
+            .unwrap()
+            .is_prime(64, &mut openssl::bn::BigNumContext::new().unwrap())
+            .unwrap()
+    );
+}
+
+#[test]
+fn rsa_small_key_test() -> Result<()> {
+    // The usual worked example: p = 61, q = 53, e = 17 gives n = 3233 and d = 2753.
+    let keypair = RsaKeyPair::from_primes(BigUint::from(61u32), BigUint::from(53u32), 17)
+        .ok_or(anyhow!("17 should be invertible"))?;
+    assert_eq!(keypair.public.n, BigUint::from(3233u32));
+    assert_eq!(keypair.private.d, BigUint::from(2753u32));
+
+    let ciphertext = keypair.public.encrypt(&BigUint::from(65u32))?;
+    assert_eq!(ciphertext, BigUint::from(2790u32));
+    assert_eq!(keypair.private.decrypt(&ciphertext)?, BigUint::from(65u32));
+    assert_eq!(
+        keypair.private.decrypt_crt(&ciphertext)?,
+        BigUint::from(65u32)
+    );
+    assert!(keypair.public.encrypt(&BigUint::from(3233u32)).is_err());
+
+    // e = 3 doesn't work when 3 divides p - 1.
+    assert!(RsaKeyPair::from_primes(BigUint::from(61u32), BigUint::from(53u32), 3).is_none());
+    Ok(())
+}
+
+#[test]
+fn challenge_39_test() -> Result<()> {
+    use crate::mt19937::Mt19937;
+    const MESSAGE: &[u8] = b"YELLOW SUBMARINE";
+    let mut rng = Mt19937::new(39);
+    for e in [SMALL_E, STANDARD_E] {
+        let keypair = RsaKeyPair::generate(512, e, &mut rng)?;
+        assert_eq!(keypair.public.n.bits(), 512);
+        assert_eq!(keypair.public.e, BigUint::from(e));
+
+        let ciphertext = keypair.public.encrypt_bytes(MESSAGE)?;
+        assert_eq!(
+            keypair.private.decrypt_bytes(&ciphertext)?.to_string(),
+            "YELLOW SUBMARINE"
+        );
+
+        let message = BigUint::from_bytes_be(MESSAGE);
+        let ciphertext = keypair.public.encrypt(&message)?;
+        assert_eq!(
+            keypair.private.decrypt(&ciphertext)?,
+            keypair.private.decrypt_crt(&ciphertext)?
+        );
+    }
+    assert!(RsaKeyPair::generate(512, 4, &mut rng).is_err());
+    Ok(())
+}
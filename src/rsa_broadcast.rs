@@ -0,0 +1,223 @@
+// Håstad's broadcast attack (Cryptopals 40): the same message sent unpadded to e recipients who
+// all use public exponent e. CRT glues the ciphertexts into m^e mod n_1 * ... * n_e, and since m
+// is below every n_i, m^e is below their product, so that's just m^e and an integer eth root
+// finishes the job.
+
+use crate::{
+    bignum,
+    rsa::{RsaKeyPair, RsaPublicKey},
+};
+use anyhow::{Result, anyhow};
+use itertools::Itertools;
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive};
+
+/// One copy of the broadcast: who it was sent to and what went over the wire
+#[derive(Debug, Clone)]
+pub struct Intercept {
+    pub key: RsaPublicKey,
+    pub ciphertext: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastAttack {
+    /// CRT and an exact eth root gave back the message
+    Plaintext(BigUint),
+    /// Moduli `first` and `second` share a prime, so CRT can't combine them. Both keys are broken
+    /// outright instead.
+    SharedFactor {
+        first: usize,
+        second: usize,
+        factor: BigUint,
+    },
+}
+
+/// The first pair of moduli with a common factor, and that factor. A pair where one modulus
+/// divides the other (the same key twice, say) has no proper factor to offer and is skipped.
+pub fn shared_factor(keys: &[RsaPublicKey]) -> Option<(usize, usize, BigUint)> {
+    keys.iter()
+        .enumerate()
+        .tuple_combinations()
+        .find_map(|((i, left), (j, right))| {
+            let factor = bignum::gcd(&left.n, &right.n);
+            (!factor.is_one() && factor != left.n && factor != right.n).then_some((i, j, factor))
+        })
+}
+
+/// The first pair of intercepts sent to the same modulus
+fn duplicate_key(keys: &[RsaPublicKey]) -> Option<(usize, usize)> {
+    keys.iter()
+        .enumerate()
+        .tuple_combinations()
+        .find_map(|((i, left), (j, right))| (left.n == right.n).then_some((i, j)))
+}
+
+/// Recover a message from at least e encryptions of it under distinct keys that share a small
+/// public exponent e
+pub fn hastad_broadcast(intercepts: &[Intercept]) -> Result<BroadcastAttack> {
+    let first = intercepts.first().ok_or(anyhow!("no ciphertexts"))?;
+    if intercepts
+        .iter()
+        .any(|intercept| intercept.key.e != first.key.e)
+    {
+        return Err(anyhow!("the keys don't share a public exponent"));
+    }
+    let e = first
+        .key
+        .e
+        .to_u32()
+        .ok_or(anyhow!("public exponent {} is too large", first.key.e))?;
+    if intercepts.len() < e as usize {
+        return Err(anyhow!(
+            "need {} ciphertexts for e = {}, got {}",
+            e,
+            e,
+            intercepts.len()
+        ));
+    }
+
+    let congruences: Vec<_> = intercepts
+        .iter()
+        .map(|intercept| (intercept.ciphertext.clone(), intercept.key.n.clone()))
+        .collect();
+    let Some(combined) = bignum::crt(&congruences) else {
+        let keys: Vec<_> = intercepts
+            .iter()
+            .map(|intercept| intercept.key.clone())
+            .collect();
+        if let Some((first, second)) = duplicate_key(&keys) {
+            return Err(anyhow!(
+                "ciphertexts {} and {} were sent to the same key",
+                first,
+                second
+            ));
+        }
+        let (first, second, factor) =
+            shared_factor(&keys).ok_or(anyhow!("CRT failed but no two moduli share a factor"))?;
+        return Ok(BroadcastAttack::SharedFactor {
+            first,
+            second,
+            factor,
+        });
+    };
+    bignum::exact_nth_root(&combined, e)
+        .map(BroadcastAttack::Plaintext)
+        .ok_or(anyhow!(
+            "no exact root; were these really the same message?"
+        ))
+}
+
+/// Rebuild the private key for a modulus once one of its primes is known
+pub fn break_key(key: &RsaPublicKey, factor: &BigUint) -> Option<RsaKeyPair> {
+    let e = key.e.to_u32()?;
+    RsaKeyPair::from_primes(factor.clone(), &key.n / factor, e)
+}
+
+#[cfg(test)]
+fn broadcast(keys: &[RsaKeyPair], message: &BigUint) -> Result<Vec<Intercept>> {
+    keys.iter()
+        .map(|keypair| {
+            Ok(Intercept {
+                ciphertext: keypair.public.encrypt(message)?,
+                key: keypair.public.clone(),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn challenge_40_test() -> Result<()> {
+    use crate::{mt19937::Mt19937, rsa::SMALL_E};
+    let mut rng = Mt19937::new(40);
+    let keys = (0..3)
+        .map(|_| RsaKeyPair::generate(512, SMALL_E, &mut rng))
+        .collect::<Result<Vec<_>>>()?;
+    let message = BigUint::from_bytes_be(b"attack at dawn, bring snacks");
+    let intercepts = broadcast(&keys, &message)?;
+    assert_eq!(
+        hastad_broadcast(&intercepts)?,
+        BroadcastAttack::Plaintext(message.clone())
+    );
+    // Two copies aren't enough for a cube root.
+    assert!(hastad_broadcast(&intercepts[..2]).is_err());
+    Ok(())
+}
+
+#[test]
+fn hastad_broadcast_larger_exponent_test() -> Result<()> {
+    use crate::mt19937::Mt19937;
+    let mut rng = Mt19937::new(5);
+    let keys = (0..5)
+        .map(|_| RsaKeyPair::generate(256, 5, &mut rng))
+        .collect::<Result<Vec<_>>>()?;
+    let message = BigUint::from_bytes_be(b"five is still small");
+    let intercepts = broadcast(&keys, &message)?;
+    assert_eq!(
+        hastad_broadcast(&intercepts)?,
+        BroadcastAttack::Plaintext(message)
+    );
+    Ok(())
+}
+
+#[test]
+fn hastad_broadcast_shared_factor_test() -> Result<()> {
+    use crate::{
+        mt19937::Mt19937,
+        rsa::{self, SMALL_E},
+    };
+    let mut rng = Mt19937::new(7);
+    // Keep drawing until 3 works as an exponent for every prime involved.
+    let mut prime = || loop {
+        let p = rsa::generate_prime(128, &mut rng);
+        if !(&p % SMALL_E).is_one() {
+            return p;
+        }
+    };
+    let shared = prime();
+    let keys = [
+        RsaKeyPair::from_primes(prime(), prime(), SMALL_E),
+        RsaKeyPair::from_primes(shared.clone(), prime(), SMALL_E),
+        RsaKeyPair::from_primes(prime(), shared.clone(), SMALL_E),
+    ]
+    .into_iter()
+    .collect::<Option<Vec<_>>>()
+    .ok_or(anyhow!("bad test primes"))?;
+
+    let message = BigUint::from_bytes_be(b"reused prime");
+    let intercepts = broadcast(&keys, &message)?;
+    let BroadcastAttack::SharedFactor {
+        first,
+        second,
+        factor,
+    } = hastad_broadcast(&intercepts)?
+    else {
+        panic!("CRT shouldn't work on these moduli");
+    };
+    assert_eq!((first, second, &factor), (1, 2, &shared));
+
+    // Either key falls to the shared prime.
+    for index in [first, second] {
+        let broken = break_key(&intercepts[index].key, &factor).ok_or(anyhow!("bad factor"))?;
+        assert_eq!(
+            broken.private.decrypt(&intercepts[index].ciphertext)?,
+            message
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn hastad_broadcast_duplicate_key_test() -> Result<()> {
+    use crate::{mt19937::Mt19937, rsa::SMALL_E};
+    let mut rng = Mt19937::new(41);
+    let keypair = RsaKeyPair::generate(256, SMALL_E, &mut rng)?;
+    let other = RsaKeyPair::generate(256, SMALL_E, &mut rng)?;
+    let keys = [keypair.clone(), other, keypair];
+    let intercepts = broadcast(&keys, &BigUint::from_bytes_be(b"twice"))?;
+
+    let public: Vec<_> = keys.iter().map(|keypair| keypair.public.clone()).collect();
+    assert_eq!(shared_factor(&public), None);
+    let error = hastad_broadcast(&intercepts).unwrap_err();
+    assert!(error.to_string().contains("same key"));
+    Ok(())
+}
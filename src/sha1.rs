@@ -0,0 +1,131 @@
+// SHA-1 (FIPS 180-4), written out by hand so its registers and padding can be poked at.
+
+use crate::{
+    hash::{Compression, Endian, Hash, MdHash},
+    hmac::Hmac,
+    length_extension::SecretPrefixMac,
+};
+
+pub const INITIAL_STATE: [u32; 5] = [
+    0x6745_2301,
+    0xefcd_ab89,
+    0x98ba_dcfe,
+    0x1032_5476,
+    0xc3d2_e1f0,
+];
+
+/// SHA-1's registers h0..h4 and its compression function
+#[derive(Clone, Debug)]
+pub struct Sha1Compression;
+
+pub type Sha1 = MdHash<Sha1Compression>;
+
+impl Compression for Sha1Compression {
+    type State = [u32; 5];
+    const INITIAL_STATE: [u32; 5] = INITIAL_STATE;
+    const ENDIAN: Endian = Endian::Big;
+
+    fn compress(state: &mut [u32; 5], block: &[u8]) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).take(16).enumerate() {
+            w[i] = Endian::Big.read_word(word);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = *state;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (register, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *register = register.wrapping_add(value);
+        }
+    }
+}
+
+/// Secret-prefix MAC: SHA1(key || message)
+pub fn secret_prefix_mac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    SecretPrefixMac::<Sha1>::new(key).sign(message)
+}
+
+pub fn verify_secret_prefix_mac(key: &[u8], message: &[u8], mac: &[u8]) -> bool {
+    SecretPrefixMac::<Sha1>::new(key).verify(message, mac)
+}
+
+/// HMAC-SHA1 (RFC 2104)
+pub fn hmac_sha1(key: &[u8], message: &[u8]) -> Vec<u8> {
+    Hmac::<Sha1>::mac(key, message)
+}
+
+#[test]
+fn sha1_fips_180_test() {
+    use crate::traits::BytesHexExt;
+    const VECTORS: [(&str, &str); 4] = [
+        ("", "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+        ("abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+        (
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+        ),
+        (
+            "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+            "a49b2446a02c645bf419f995b67091253a04a259",
+        ),
+    ];
+    for (input, expected) in VECTORS {
+        assert_eq!(Sha1::digest(input.as_bytes()).to_hex(), expected);
+    }
+
+    // One million 'a's, fed in uneven pieces to exercise the buffering.
+    let mut sha1 = Sha1::new();
+    let data = vec![b'a'; 1_000_000];
+    data.chunks(999).for_each(|chunk| sha1.update(chunk));
+    assert_eq!(
+        sha1.finalize().to_hex(),
+        "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+    );
+}
+
+#[test]
+fn sha1_matches_openssl_test() {
+    crate::hash::check_against_reference::<Sha1>(|data| openssl::sha::sha1(data).to_vec());
+}
+
+#[test]
+fn challenge_28_test() {
+    use crate::utils;
+    const MESSAGE: &[u8] =
+        b"comment1=cooking%20MCs;userdata=foo;comment2=%20like%20a%20pound%20of%20bacon";
+    let key = utils::generate_16_byte_key();
+    let mac = secret_prefix_mac(&key, MESSAGE);
+    assert!(verify_secret_prefix_mac(&key, MESSAGE, &mac));
+
+    // Changing the message or the key breaks the MAC.
+    let mut tampered = MESSAGE.to_vec();
+    tampered[0] ^= 1;
+    assert!(!verify_secret_prefix_mac(&key, &tampered, &mac));
+    assert!(!verify_secret_prefix_mac(
+        b"YELLOW SUBMARINE",
+        MESSAGE,
+        &mac
+    ));
+    assert_ne!(mac, Sha1::digest(MESSAGE));
+}
@@ -0,0 +1,548 @@
+// Secure Remote Password, SRP-6a flavour (Cryptopals 36). Client and server are both state
+// machines that take one message and hand back their answer; `serve` and `login` drive them over
+// a channel.
+
+use crate::{
+    bignum::{self, BigUintExt},
+    channel::Endpoint,
+    dh::{MODP_1536_G, MODP_1536_P},
+    hash::Hash,
+    hmac::{self, Hmac},
+    sha256::Sha256,
+};
+use anyhow::{Result, anyhow};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use rand::RngCore;
+use std::collections::HashMap;
+
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrpParams {
+    pub n: BigUint,
+    pub g: BigUint,
+    pub k: BigUint,
+}
+
+impl SrpParams {
+    /// SRP-6a derives the multiplier from the group: k = SHA256(N || PAD(g))
+    pub fn new(n: BigUint, g: BigUint) -> Self {
+        let mut sha256 = Sha256::new();
+        sha256.update(&n.to_bytes_be());
+        sha256.update(&pad(&n, &g));
+        let k = BigUint::from_bytes_be(&sha256.finalize());
+        SrpParams { n, g, k }
+    }
+}
+
+impl Default for SrpParams {
+    /// The NIST prime with g = 2, as the challenge sets it up
+    fn default() -> Self {
+        SrpParams::new(
+            BigUint::try_from_hex(MODP_1536_P).expect("MODP prime is valid hex"),
+            BigUint::from(MODP_1536_G),
+        )
+    }
+}
+
+/// `value` as big-endian bytes, left-padded with zeros to the length of N
+fn pad(n: &BigUint, value: &BigUint) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let mut padded = vec![0; n.to_bytes_be().len().saturating_sub(bytes.len())];
+    padded.extend(bytes);
+    padded
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SrpMessage {
+    /// C->S: I, A = g^a mod N
+    Hello { email: String, public: BigUint },
+    /// S->C: salt, B = kv + g^b mod N
+    Challenge { salt: Vec<u8>, public: BigUint },
+    /// C->S: HMAC-SHA256(K, salt)
+    Proof(Vec<u8>),
+    /// S->C: whether the proof checked out (or straight after Hello, for unknown users)
+    Verdict(bool),
+}
+
+/// x = SHA256(salt || password), as an integer
+pub fn password_hash(salt: &[u8], password: &[u8]) -> BigUint {
+    let mut sha256 = Sha256::new();
+    sha256.update(salt);
+    sha256.update(password);
+    BigUint::from_bytes_be(&sha256.finalize())
+}
+
+/// u = SHA256(PAD(A) || PAD(B)), as an integer
+pub fn scramble(params: &SrpParams, client_public: &BigUint, server_public: &BigUint) -> BigUint {
+    let mut sha256 = Sha256::new();
+    sha256.update(&pad(&params.n, client_public));
+    sha256.update(&pad(&params.n, server_public));
+    BigUint::from_bytes_be(&sha256.finalize())
+}
+
+/// K = SHA256(S)
+pub fn session_key(secret: &BigUint) -> Vec<u8> {
+    Sha256::digest(&secret.to_bytes_be())
+}
+
+/// The proof either side expects: HMAC-SHA256(K, salt)
+pub fn proof(key: &[u8], salt: &[u8]) -> Vec<u8> {
+    Hmac::<Sha256>::mac(key, salt)
+}
+
+/// A fresh random salt
+pub fn random_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// A random private exponent and g to its power
+pub fn ephemeral_keypair(params: &SrpParams) -> (BigUint, BigUint) {
+    let private = bignum::random_below(&mut rand::rng(), &params.n);
+    let public = bignum::modpow(&params.g, &private, &params.n);
+    (private, public)
+}
+
+/// Messages that can carry the server's yes or no, so `serve` can turn away a confused client
+pub trait Verdict {
+    fn verdict(accepted: bool) -> Self;
+}
+
+impl Verdict for SrpMessage {
+    fn verdict(accepted: bool) -> Self {
+        SrpMessage::Verdict(accepted)
+    }
+}
+
+/// Anything that can play the server side of a login
+pub trait SrpServe<M = SrpMessage> {
+    fn handle(&mut self, message: M) -> Result<M>;
+}
+
+/// Anything that can play the client side of a login
+pub trait SrpLogin<M = SrpMessage> {
+    /// The opening message
+    fn start(&mut self) -> M;
+    /// Feed in the server's reply. Returns the next message to send, if any.
+    fn handle(&mut self, message: M) -> Result<Option<M>>;
+    /// Whether the server let us in, once it has said so
+    fn authenticated(&self) -> Option<bool>;
+}
+
+/// All the server keeps about a user
+pub(crate) struct Record {
+    pub salt: Vec<u8>,
+    pub verifier: BigUint,
+}
+
+impl Record {
+    /// A fresh salt and v = g^x mod N. The password itself is forgotten.
+    pub fn new(params: &SrpParams, password: &[u8]) -> Self {
+        let salt = random_salt();
+        let x = password_hash(&salt, password);
+        let verifier = bignum::modpow(&params.g, &x, &params.n);
+        Record { salt, verifier }
+    }
+}
+
+pub(crate) enum ServerState {
+    Idle,
+    AwaitingProof { expected: Vec<u8> },
+}
+
+pub(crate) enum ClientState {
+    Start,
+    AwaitingChallenge { a: BigUint, public: BigUint },
+    AwaitingVerdict,
+    Done(bool),
+}
+
+impl ClientState {
+    pub fn verdict(&self) -> Option<bool> {
+        match self {
+            ClientState::Done(accepted) => Some(*accepted),
+            _ => None,
+        }
+    }
+}
+
+pub struct SrpServer {
+    params: SrpParams,
+    users: HashMap<String, Record>,
+    state: ServerState,
+    check_public: bool,
+}
+
+impl SrpServer {
+    pub fn new(params: SrpParams) -> Self {
+        SrpServer {
+            params,
+            users: HashMap::new(),
+            state: ServerState::Idle,
+            check_public: false,
+        }
+    }
+
+    /// Turn away any A that is 0 mod N, which would pin the shared secret to 0
+    pub fn hardened(mut self) -> Self {
+        self.check_public = true;
+        self
+    }
+
+    pub fn register(&mut self, email: &str, password: &[u8]) {
+        self.users
+            .insert(email.to_string(), Record::new(&self.params, password));
+    }
+}
+
+impl SrpServe for SrpServer {
+    fn handle(&mut self, message: SrpMessage) -> Result<SrpMessage> {
+        match (&self.state, message) {
+            // A hello always starts over, so a client that walks away mid-login can't lock out
+            // the next one.
+            (_, SrpMessage::Hello { email, public }) => {
+                self.state = ServerState::Idle;
+                let Some(record) = self.users.get(&email) else {
+                    return Ok(SrpMessage::Verdict(false));
+                };
+                let SrpParams { n, k, .. } = &self.params;
+                if self.check_public && (&public % n).is_zero() {
+                    return Ok(SrpMessage::Verdict(false));
+                }
+
+                let (b, g_b) = ephemeral_keypair(&self.params);
+                let server_public = (k * &record.verifier + g_b) % n;
+                let u = scramble(&self.params, &public, &server_public);
+                // S = (A * v^u)^b mod N
+                let secret =
+                    bignum::modpow(&(&public * bignum::modpow(&record.verifier, &u, n)), &b, n);
+
+                let salt = record.salt.clone();
+                self.state = ServerState::AwaitingProof {
+                    expected: proof(&session_key(&secret), &salt),
+                };
+                Ok(SrpMessage::Challenge {
+                    salt,
+                    public: server_public,
+                })
+            }
+            (ServerState::AwaitingProof { expected }, SrpMessage::Proof(mac)) => {
+                let accepted = hmac::constant_time_eq(&mac, expected);
+                self.state = ServerState::Idle;
+                Ok(SrpMessage::Verdict(accepted))
+            }
+            (_, message) => {
+                self.state = ServerState::Idle;
+                Err(anyhow!("server didn't expect {:?}", message))
+            }
+        }
+    }
+}
+
+pub struct SrpClient {
+    params: SrpParams,
+    email: String,
+    password: Vec<u8>,
+    state: ClientState,
+}
+
+impl SrpClient {
+    pub fn new(params: SrpParams, email: &str, password: &[u8]) -> Self {
+        SrpClient {
+            params,
+            email: email.to_string(),
+            password: password.to_vec(),
+            state: ClientState::Start,
+        }
+    }
+}
+
+impl SrpLogin for SrpClient {
+    /// I and A = g^a mod N
+    fn start(&mut self) -> SrpMessage {
+        let (a, public) = ephemeral_keypair(&self.params);
+        self.state = ClientState::AwaitingChallenge {
+            a,
+            public: public.clone(),
+        };
+        SrpMessage::Hello {
+            email: self.email.clone(),
+            public,
+        }
+    }
+
+    fn handle(&mut self, message: SrpMessage) -> Result<Option<SrpMessage>> {
+        match (&self.state, message) {
+            (
+                ClientState::AwaitingChallenge { a, public },
+                SrpMessage::Challenge {
+                    salt,
+                    public: server_public,
+                },
+            ) => {
+                let SrpParams { n, g, k } = &self.params;
+                // B = 0 mod N would let the server pin our secret, so SRP-6a gives up here.
+                if (&server_public % n).is_zero() {
+                    self.state = ClientState::Done(false);
+                    return Err(anyhow!("server sent B = 0 mod N"));
+                }
+                let u = scramble(&self.params, public, &server_public);
+                let x = password_hash(&salt, &self.password);
+                // S = (B - k * g^x)^(a + u * x) mod N, kept non-negative by adding k * N first.
+                let base = (&server_public + k * n - k * bignum::modpow(g, &x, n)) % n;
+                let secret = bignum::modpow(&base, &(a + u * x), n);
+
+                self.state = ClientState::AwaitingVerdict;
+                Ok(Some(SrpMessage::Proof(proof(&session_key(&secret), &salt))))
+            }
+            (
+                ClientState::AwaitingChallenge { .. } | ClientState::AwaitingVerdict,
+                SrpMessage::Verdict(accepted),
+            ) => {
+                self.state = ClientState::Done(accepted);
+                Ok(None)
+            }
+            (_, message) => Err(anyhow!("client didn't expect {:?}", message)),
+        }
+    }
+
+    fn authenticated(&self) -> Option<bool> {
+        self.state.verdict()
+    }
+}
+
+/// Cryptopals 37: log in without the password by sending A = multiple * N. The server's
+/// S = (A * v^u)^b mod N is then 0, and so is ours.
+pub struct ZeroKeyClient {
+    email: String,
+    public: BigUint,
+    authenticated: Option<bool>,
+}
+
+impl ZeroKeyClient {
+    pub fn new(params: &SrpParams, email: &str, multiple: u32) -> Self {
+        ZeroKeyClient {
+            email: email.to_string(),
+            public: &params.n * multiple,
+            authenticated: None,
+        }
+    }
+}
+
+impl SrpLogin for ZeroKeyClient {
+    fn start(&mut self) -> SrpMessage {
+        SrpMessage::Hello {
+            email: self.email.clone(),
+            public: self.public.clone(),
+        }
+    }
+
+    fn handle(&mut self, message: SrpMessage) -> Result<Option<SrpMessage>> {
+        match message {
+            SrpMessage::Challenge { salt, .. } => Ok(Some(SrpMessage::Proof(proof(
+                &session_key(&BigUint::ZERO),
+                &salt,
+            )))),
+            SrpMessage::Verdict(accepted) => {
+                self.authenticated = Some(accepted);
+                Ok(None)
+            }
+            message => Err(anyhow!("client didn't expect {:?}", message)),
+        }
+    }
+
+    fn authenticated(&self) -> Option<bool> {
+        self.authenticated
+    }
+}
+
+/// Answer login attempts until the client side hangs up. A message the server can't make sense
+/// of fails that login, not the ones after it.
+pub fn serve<M: Verdict, S: SrpServe<M> + ?Sized>(
+    link: &Endpoint<M>,
+    server: &mut S,
+) -> Result<()> {
+    while let Ok(message) = link.recv() {
+        let reply = server.handle(message).unwrap_or(M::verdict(false));
+        link.send(reply)?;
+    }
+    Ok(())
+}
+
+/// Run one login over `link` and report whether the server accepted it
+pub fn login<M, C: SrpLogin<M> + ?Sized>(link: &Endpoint<M>, client: &mut C) -> Result<bool> {
+    link.send(client.start())?;
+    loop {
+        match client.handle(link.recv()?)? {
+            Some(reply) => link.send(reply)?,
+            None => {
+                return client
+                    .authenticated()
+                    .ok_or(anyhow!("login ended without a verdict"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+const EMAIL: &str = "alice@example.com";
+
+#[cfg(test)]
+const PASSWORD: &[u8] = b"YELLOW SUBMARINE";
+
+// Serve one client after another from a single server thread.
+#[cfg(test)]
+fn run_logins(mut server: SrpServer, clients: Vec<Box<dyn SrpLogin>>) -> Result<Vec<bool>> {
+    use crate::channel;
+    use std::thread;
+
+    let (client_link, server_link) = channel::connect();
+    thread::scope(|scope| {
+        let server_thread = scope.spawn(move || serve(&server_link, &mut server));
+        let results = clients
+            .into_iter()
+            .map(|mut client| login(&client_link, client.as_mut()))
+            .collect::<Result<Vec<_>>>();
+        drop(client_link);
+        server_thread
+            .join()
+            .map_err(|_| anyhow!("server panicked"))??;
+        results
+    })
+}
+
+#[cfg(test)]
+fn registered_server() -> SrpServer {
+    let mut server = SrpServer::new(SrpParams::default());
+    server.register(EMAIL, PASSWORD);
+    server
+}
+
+#[cfg(test)]
+fn honest_client(email: &str, password: &[u8]) -> Box<dyn SrpLogin> {
+    Box::new(SrpClient::new(SrpParams::default(), email, password))
+}
+
+#[test]
+fn challenge_36_test() -> Result<()> {
+    let results = run_logins(
+        registered_server(),
+        vec![
+            honest_client(EMAIL, PASSWORD),
+            honest_client(EMAIL, b"yellow submarine"),
+            honest_client(EMAIL, PASSWORD),
+            // Unknown users are turned away before any challenge.
+            honest_client("mallory@example.com", PASSWORD),
+        ],
+    )?;
+    assert_eq!(results, [true, false, true, false]);
+    Ok(())
+}
+
+#[test]
+fn challenge_37_test() -> Result<()> {
+    let params = SrpParams::default();
+    let attackers = || -> Vec<Box<dyn SrpLogin>> {
+        (0..4)
+            .map(|multiple| Box::new(ZeroKeyClient::new(&params, EMAIL, multiple)) as _)
+            .collect()
+    };
+
+    let results = run_logins(registered_server(), attackers())?;
+    assert_eq!(results, [true; 4]);
+
+    // The hardened server sees through it, and still lets the real user in.
+    let mut clients = attackers();
+    clients.push(honest_client(EMAIL, PASSWORD));
+    let results = run_logins(registered_server().hardened(), clients)?;
+    assert_eq!(results, [false, false, false, false, true]);
+    Ok(())
+}
+
+#[test]
+fn srp_server_stores_no_password_test() {
+    let mut server = SrpServer::new(SrpParams::default());
+    server.register(EMAIL, b"hunter2");
+    let record = &server.users[EMAIL];
+    let x = password_hash(&record.salt, b"hunter2");
+    assert_eq!(
+        record.verifier,
+        bignum::modpow(&server.params.g, &x, &server.params.n)
+    );
+    // Registering again picks a new salt.
+    let old_salt = record.salt.clone();
+    server.register(EMAIL, b"hunter2");
+    assert_ne!(server.users[EMAIL].salt, old_salt);
+}
+
+#[test]
+fn srp_serve_survives_bad_client_test() -> Result<()> {
+    use crate::channel;
+    use std::thread;
+
+    let (client_link, server_link) = channel::connect();
+    let mut server = registered_server();
+    thread::scope(|scope| {
+        let server_thread = scope.spawn(move || serve(&server_link, &mut server));
+        // A proof out of nowhere gets a refusal instead of taking the server down.
+        client_link.send(SrpMessage::Proof(vec![0; 32]))?;
+        assert_eq!(client_link.recv()?, SrpMessage::Verdict(false));
+        let mut client = SrpClient::new(SrpParams::default(), EMAIL, PASSWORD);
+        assert!(login(&client_link, &mut client)?);
+        drop(client_link);
+        server_thread
+            .join()
+            .map_err(|_| anyhow!("server panicked"))?
+    })
+}
+
+#[test]
+fn srp_6a_params_test() {
+    // k = SHA256(N || PAD(g)), worked out independently for the default group
+    let params = SrpParams::default();
+    assert_eq!(
+        params.k.to_hex(),
+        "04f99e0d74ef9882cd817dd7c59eaea1af70194e52baa52a99ec0fea0097e4e1"
+    );
+    assert_eq!(pad(&params.n, &params.g).len(), 192);
+}
+
+#[test]
+fn srp_client_rejects_zero_server_public_test() {
+    let params = SrpParams::default();
+    for multiple in [0u32, 1, 2] {
+        let mut client = SrpClient::new(params.clone(), EMAIL, PASSWORD);
+        client.start();
+        let challenge = SrpMessage::Challenge {
+            salt: vec![0; SALT_LEN],
+            public: &params.n * multiple,
+        };
+        assert!(client.handle(challenge).is_err());
+        assert_eq!(client.authenticated(), Some(false));
+    }
+}
+
+#[test]
+fn srp_serve_survives_abandoned_login_test() -> Result<()> {
+    use crate::channel;
+    use std::thread;
+
+    let (client_link, server_link) = channel::connect();
+    let mut server = registered_server();
+    thread::scope(|scope| {
+        let server_thread = scope.spawn(move || serve(&server_link, &mut server));
+        // Walk away after the challenge, as a client that balks at B does.
+        let mut quitter = SrpClient::new(SrpParams::default(), EMAIL, PASSWORD);
+        client_link.send(quitter.start())?;
+        assert!(matches!(client_link.recv()?, SrpMessage::Challenge { .. }));
+        let mut client = SrpClient::new(SrpParams::default(), EMAIL, PASSWORD);
+        assert!(login(&client_link, &mut client)?);
+        drop(client_link);
+        server_thread
+            .join()
+            .map_err(|_| anyhow!("server panicked"))?
+    })
+}
@@ -0,0 +1,328 @@
+// Simplified SRP (Cryptopals 38): B = g^b with no verifier mixed in, and u sent in the clear.
+// That lets a fake server pick b and u itself, and check password guesses offline against one
+// captured login.
+
+use crate::{
+    bignum, hmac,
+    srp::{self, ClientState, Record, ServerState, SrpLogin, SrpParams, SrpServe, Verdict},
+};
+use anyhow::{Result, anyhow};
+use num_bigint::BigUint;
+use std::{collections::HashMap, fs, path::Path, thread};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimpleSrpMessage {
+    /// C->S: I, A = g^a mod N
+    Hello { email: String, public: BigUint },
+    /// S->C: salt, B = g^b mod N, and a random 128-bit u
+    Challenge {
+        salt: Vec<u8>,
+        public: BigUint,
+        u: BigUint,
+    },
+    /// C->S: HMAC-SHA256(K, salt)
+    Proof(Vec<u8>),
+    /// S->C: whether the proof checked out
+    Verdict(bool),
+}
+
+impl Verdict for SimpleSrpMessage {
+    fn verdict(accepted: bool) -> Self {
+        SimpleSrpMessage::Verdict(accepted)
+    }
+}
+
+pub struct SimpleSrpServer {
+    params: SrpParams,
+    users: HashMap<String, Record>,
+    state: ServerState,
+}
+
+impl SimpleSrpServer {
+    pub fn new(params: SrpParams) -> Self {
+        SimpleSrpServer {
+            params,
+            users: HashMap::new(),
+            state: ServerState::Idle,
+        }
+    }
+
+    pub fn register(&mut self, email: &str, password: &[u8]) {
+        self.users
+            .insert(email.to_string(), Record::new(&self.params, password));
+    }
+}
+
+impl SrpServe<SimpleSrpMessage> for SimpleSrpServer {
+    fn handle(&mut self, message: SimpleSrpMessage) -> Result<SimpleSrpMessage> {
+        match (&self.state, message) {
+            // As in `SrpServer`, a hello starts over from whatever came before.
+            (_, SimpleSrpMessage::Hello { email, public }) => {
+                self.state = ServerState::Idle;
+                let Some(record) = self.users.get(&email) else {
+                    return Ok(SimpleSrpMessage::Verdict(false));
+                };
+                let n = &self.params.n;
+                let (b, server_public) = srp::ephemeral_keypair(&self.params);
+                let u = BigUint::from(rand::random::<u128>());
+                // S = (A * v^u)^b mod N
+                let secret =
+                    bignum::modpow(&(&public * bignum::modpow(&record.verifier, &u, n)), &b, n);
+
+                let salt = record.salt.clone();
+                self.state = ServerState::AwaitingProof {
+                    expected: srp::proof(&srp::session_key(&secret), &salt),
+                };
+                Ok(SimpleSrpMessage::Challenge {
+                    salt,
+                    public: server_public,
+                    u,
+                })
+            }
+            (ServerState::AwaitingProof { expected }, SimpleSrpMessage::Proof(mac)) => {
+                let accepted = hmac::constant_time_eq(&mac, expected);
+                self.state = ServerState::Idle;
+                Ok(SimpleSrpMessage::Verdict(accepted))
+            }
+            (_, message) => {
+                self.state = ServerState::Idle;
+                Err(anyhow!("server didn't expect {:?}", message))
+            }
+        }
+    }
+}
+
+pub struct SimpleSrpClient {
+    params: SrpParams,
+    email: String,
+    password: Vec<u8>,
+    state: ClientState,
+}
+
+impl SimpleSrpClient {
+    pub fn new(params: SrpParams, email: &str, password: &[u8]) -> Self {
+        SimpleSrpClient {
+            params,
+            email: email.to_string(),
+            password: password.to_vec(),
+            state: ClientState::Start,
+        }
+    }
+}
+
+impl SrpLogin<SimpleSrpMessage> for SimpleSrpClient {
+    fn start(&mut self) -> SimpleSrpMessage {
+        let (a, public) = srp::ephemeral_keypair(&self.params);
+        self.state = ClientState::AwaitingChallenge {
+            a,
+            public: public.clone(),
+        };
+        SimpleSrpMessage::Hello {
+            email: self.email.clone(),
+            public,
+        }
+    }
+
+    fn handle(&mut self, message: SimpleSrpMessage) -> Result<Option<SimpleSrpMessage>> {
+        match (&self.state, message) {
+            (
+                ClientState::AwaitingChallenge { a, .. },
+                SimpleSrpMessage::Challenge { salt, public, u },
+            ) => {
+                // S = B^(a + u * x) mod N
+                let x = srp::password_hash(&salt, &self.password);
+                let secret = bignum::modpow(&public, &(a + u * x), &self.params.n);
+                self.state = ClientState::AwaitingVerdict;
+                Ok(Some(SimpleSrpMessage::Proof(srp::proof(
+                    &srp::session_key(&secret),
+                    &salt,
+                ))))
+            }
+            (
+                ClientState::AwaitingChallenge { .. } | ClientState::AwaitingVerdict,
+                SimpleSrpMessage::Verdict(accepted),
+            ) => {
+                self.state = ClientState::Done(accepted);
+                Ok(None)
+            }
+            (_, message) => Err(anyhow!("client didn't expect {:?}", message)),
+        }
+    }
+
+    fn authenticated(&self) -> Option<bool> {
+        self.state.verdict()
+    }
+}
+
+/// Everything the fake server needs from one login to go guessing
+#[derive(Debug, Clone)]
+pub struct CapturedLogin {
+    pub email: String,
+    pub salt: Vec<u8>,
+    pub client_public: BigUint,
+    pub mac: Vec<u8>,
+}
+
+/// Poses as the server with b = 1 and u = 1, so the client's secret comes out as
+/// S = g^(a + x) = A * g^x mod N, which anyone holding A can check a guess for x against.
+pub struct MitmServer {
+    params: SrpParams,
+    salt: Vec<u8>,
+    hello: Option<(String, BigUint)>,
+    pub captured: Option<CapturedLogin>,
+}
+
+impl MitmServer {
+    pub fn new(params: SrpParams) -> Self {
+        MitmServer {
+            params,
+            salt: srp::random_salt(),
+            hello: None,
+            captured: None,
+        }
+    }
+}
+
+impl SrpServe<SimpleSrpMessage> for MitmServer {
+    fn handle(&mut self, message: SimpleSrpMessage) -> Result<SimpleSrpMessage> {
+        match message {
+            SimpleSrpMessage::Hello { email, public } => {
+                self.hello = Some((email, public));
+                Ok(SimpleSrpMessage::Challenge {
+                    salt: self.salt.clone(),
+                    public: self.params.g.clone(),
+                    u: BigUint::from(1u32),
+                })
+            }
+            SimpleSrpMessage::Proof(mac) => {
+                let (email, client_public) =
+                    self.hello.take().ok_or(anyhow!("proof before hello"))?;
+                self.captured = Some(CapturedLogin {
+                    email,
+                    salt: self.salt.clone(),
+                    client_public,
+                    mac,
+                });
+                // Nothing to check it against; the login just fails like a typo would.
+                Ok(SimpleSrpMessage::Verdict(false))
+            }
+            message => Err(anyhow!("server didn't expect {:?}", message)),
+        }
+    }
+}
+
+/// One password per line, blank lines skipped
+pub fn load_wordlist<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn guess_matches(params: &SrpParams, login: &CapturedLogin, guess: &str) -> bool {
+    let x = srp::password_hash(&login.salt, guess.as_bytes());
+    let secret = (&login.client_public * bignum::modpow(&params.g, &x, &params.n)) % &params.n;
+    srp::proof(&srp::session_key(&secret), &login.salt) == login.mac
+}
+
+/// Try every word against a login captured by `MitmServer`, split across all cores
+pub fn crack_password(
+    params: &SrpParams,
+    login: &CapturedLogin,
+    wordlist: &[String],
+) -> Option<String> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = wordlist.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let workers: Vec<_> = wordlist
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .find(|word| guess_matches(params, login, word))
+                        .cloned()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().ok().flatten())
+            .next()
+    })
+}
+
+#[cfg(test)]
+const EMAIL: &str = "alice@example.com";
+
+#[cfg(test)]
+fn login_once<S: SrpServe<SimpleSrpMessage> + Send>(
+    server: &mut S,
+    password: &[u8],
+) -> Result<bool> {
+    use crate::{
+        channel,
+        srp::{login, serve},
+    };
+
+    let (client_link, server_link) = channel::connect();
+    thread::scope(|scope| {
+        let server_thread = scope.spawn(move || serve(&server_link, server));
+        let mut client = SimpleSrpClient::new(SrpParams::default(), EMAIL, password);
+        let result = login(&client_link, &mut client);
+        drop(client_link);
+        server_thread
+            .join()
+            .map_err(|_| anyhow!("server panicked"))??;
+        result
+    })
+}
+
+#[test]
+fn simple_srp_login_test() -> Result<()> {
+    let mut server = SimpleSrpServer::new(SrpParams::default());
+    server.register(EMAIL, b"hunter2");
+    assert!(login_once(&mut server, b"hunter2")?);
+    assert!(!login_once(&mut server, b"hunter3")?);
+    Ok(())
+}
+
+#[test]
+fn simple_srp_abandoned_login_test() -> Result<()> {
+    let mut server = SimpleSrpServer::new(SrpParams::default());
+    server.register(EMAIL, b"hunter2");
+    // A client that takes the challenge and never sends a proof doesn't spoil the next login.
+    let mut quitter = SimpleSrpClient::new(SrpParams::default(), EMAIL, b"hunter2");
+    assert!(matches!(
+        server.handle(quitter.start())?,
+        SimpleSrpMessage::Challenge { .. }
+    ));
+    assert!(login_once(&mut server, b"hunter2")?);
+    Ok(())
+}
+
+#[test]
+fn challenge_38_test() -> Result<()> {
+    use rand::seq::IndexedRandom;
+    let wordlist = load_wordlist(concat!(env!("CARGO_MANIFEST_DIR"), "/data/wordlist.txt"))?;
+    let password = wordlist.choose(&mut rand::rng()).unwrap().clone();
+
+    let mut mallory = MitmServer::new(SrpParams::default());
+    assert!(!login_once(&mut mallory, password.as_bytes())?);
+    let captured = mallory.captured.ok_or(anyhow!("nothing captured"))?;
+    assert_eq!(captured.email, EMAIL);
+
+    let params = SrpParams::default();
+    assert_eq!(
+        crack_password(&params, &captured, &wordlist),
+        Some(password)
+    );
+    // A password that isn't in the list stays safe.
+    let mut mallory = MitmServer::new(SrpParams::default());
+    login_once(&mut mallory, b"correct horse battery staple")?;
+    let captured = mallory.captured.ok_or(anyhow!("nothing captured"))?;
+    assert_eq!(crack_password(&params, &captured, &wordlist), None);
+    Ok(())
+}
@@ -110,7 +110,7 @@ pub fn require_eq<T: PartialEq + std::fmt::Debug>(left: T, right: T) -> Result<(
 }
 
 pub fn pad_to(data: &mut Vec<u8>, length: usize) {
-    data.extend(std::iter::repeat(0).take(length - data.len()));
+    data.extend(std::iter::repeat_n(0, length - data.len()));
 }
 
 pub fn add_padding(data: &[u8], length: usize) -> Vec<u8> {
@@ -127,11 +127,20 @@ pub fn random_byte_vec() -> Vec<u8> {
 }
 
 pub fn random_byte() -> u8 {
-    rand::rng().random_range(33..126) as u8
+    random_byte_from(&mut rand::rng())
+}
+
+// Same as random_byte, but drawn from a generator of our choosing (e.g. a seeded MT19937).
+pub fn random_byte_from<R: Rng + ?Sized>(rng: &mut R) -> u8 {
+    rng.random_range(33..126) as u8
 }
 
 pub fn generate_16_byte_key() -> Vec<u8> {
-    (0..16).map(|_| random_byte()).collect::<Vec<u8>>()
+    generate_16_byte_key_from(&mut rand::rng())
+}
+
+pub fn generate_16_byte_key_from<R: Rng + ?Sized>(rng: &mut R) -> Vec<u8> {
+    (0..16).map(|_| random_byte_from(rng)).collect::<Vec<u8>>()
 }
 //This detection mode only works when the encrypted plaintext is a string of identical bytes.
 //Because ebc uses a the same key to encrypt each block, the amound of identical bytes in the